@@ -0,0 +1,8 @@
+-- The health check is written in rust (see `src/health.rs`) and replace this module in
+-- `package.loaded` when the config is loaded. This file only exist so `:checkhealth` can find it.
+return {
+    check = function()
+        vim.health.start("CrabbyVim")
+        vim.health.error("The rust config is not loaded", { "Run `cargo build --release` in " .. vim.fn.stdpath("config") })
+    end,
+}
@@ -1,5 +1,7 @@
 //! Configure user command and export helper to create new custom user command.
 
+use nvim_oxi::api::types::{CommandArgs, CommandComplete, CommandNArgs};
+
 pub(crate) fn configure() -> nvim_oxi::Result<()> {
     create_command(
         "Move the working directory to the current file",
@@ -46,3 +48,49 @@ where
     nvim_oxi::api::create_user_command(name, cmd, &opts.build())?;
     Ok(())
 }
+
+/// Like [`create_command`] but the command accept any number of arguments that are pass to `cmd`.
+/// `complete` is called with the argument being typed and the whole command line and return the
+/// candidates, only the candidates starting with the typed argument are shown.
+///
+/// # Examples
+/// ```rust
+/// create_command_args(
+///     "Toggle LSP inlay hints for the current buffer",
+///     "CrabbyInlayHints",
+///     |_, _| vec!["on".into(), "off".into(), "toggle".into()],
+///     |args| {
+///         // ...
+///         Ok(())
+///     },
+/// )?;
+/// ```
+pub fn create_command_args<C, F>(
+    desc: &'static str,
+    name: &'static str,
+    complete: C,
+    cmd: F,
+) -> nvim_oxi::Result<()>
+where
+    C: Fn(&str, &str) -> Vec<String> + 'static,
+    F: Fn(Vec<String>) -> nvim_oxi::Result<()> + 'static,
+{
+    let mut opts = nvim_oxi::api::opts::CreateCommandOpts::builder();
+    opts.desc(desc);
+    opts.nargs(CommandNArgs::Any);
+    opts.complete(CommandComplete::CustomList(nvim_oxi::Function::from_fn(
+        move |(lead, line, _): (String, String, usize)| {
+            complete(&lead, &line)
+                .into_iter()
+                .filter(|candidate| candidate.starts_with(&lead))
+                .collect::<Vec<_>>()
+        },
+    )));
+
+    nvim_oxi::api::create_user_command(
+        name,
+        move |args: CommandArgs| cmd(args.fargs),
+        &opts.build(),
+    )?;
+    Ok(())
+}
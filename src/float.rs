@@ -0,0 +1,116 @@
+//! Helper to show reports and interactive list in a floating scratch window.
+//!
+//! # Examples
+//! ```rust
+//! let float = Float::open("Report", &["hello".into(), "world".into()])?;
+//! float.set_key("r", "Refresh the report", move || {
+//!     float.set_lines(&["goodbye".into(), "world".into()])
+//! })?;
+//! ```
+
+use mlua::{ObjectLike, Table};
+
+use crate::{keymaps::set_buf_key, options::get_option, table, vim};
+
+/// A floating window showing a scratch buffer.
+///
+/// The buffer is wipe as soon as the window is closed and `q` and `<esc>` are map to close the
+/// window.
+#[derive(Clone, Copy)]
+pub struct Float {
+    /// Handle of the scratch buffer.
+    pub buf: i64,
+    /// Handle of the floating window.
+    pub win: i64,
+}
+
+impl Float {
+    /// Open a new floating window centered in the editor with `title` and fill it with `lines`.
+    pub fn open(title: &str, lines: &[String]) -> nvim_oxi::Result<Self> {
+        let api = vim()?.get::<Table>("api")?;
+
+        let buf = api.call_function::<i64>("nvim_create_buf", (false, true))?;
+
+        let (columns, rows) = (get_option::<i64>("columns")?, get_option::<i64>("lines")?);
+        let width = (columns * 4 / 5).max(1);
+        let height = i64::try_from(lines.len())
+            .unwrap_or(i64::MAX)
+            .clamp(1, (rows * 4 / 5).max(1));
+
+        let win = api.call_function::<i64>(
+            "nvim_open_win",
+            (
+                buf,
+                true,
+                table! {
+                    relative = "editor",
+                    width = width,
+                    height = height,
+                    row = (rows - height) / 2,
+                    col = (columns - width) / 2,
+                    style = "minimal",
+                    border = "rounded",
+                    title = format!(" {title} "),
+                    title_pos = "center"
+                },
+            ),
+        )?;
+
+        let bo = vim()?.get::<Table>("bo")?.get::<Table>(buf)?;
+        bo.set("bufhidden", "wipe")?;
+        bo.set("filetype", "crabbyvim")?;
+
+        let float = Self { buf, win };
+        float.set_lines(lines)?;
+        float.set_key("q", "Close", move || float.close())?;
+        float.set_key("<esc>", "Close", move || float.close())?;
+
+        Ok(float)
+    }
+
+    /// Replace the content of the window with `lines`.
+    pub fn set_lines(&self, lines: &[String]) -> nvim_oxi::Result<()> {
+        let bo = vim()?.get::<Table>("bo")?.get::<Table>(self.buf)?;
+
+        bo.set("modifiable", true)?;
+        vim()?.get::<Table>("api")?.call_function::<()>(
+            "nvim_buf_set_lines",
+            (self.buf, 0, -1, false, lines.to_vec()),
+        )?;
+        bo.set("modifiable", false)?;
+
+        Ok(())
+    }
+
+    /// Map `key` in normal mode for this window buffer to call `callback`.
+    pub fn set_key<F>(
+        &self,
+        key: &'static str,
+        desc: &'static str,
+        callback: F,
+    ) -> nvim_oxi::Result<()>
+    where
+        F: FnMut() -> nvim_oxi::Result<()> + 'static,
+    {
+        set_buf_key(self.buf, key, desc, callback)
+    }
+
+    /// Return the line the cursor is on, starting from 1.
+    pub fn cursor_line(&self) -> nvim_oxi::Result<usize> {
+        Ok(vim()?
+            .get::<Table>("api")?
+            .call_function::<Table>("nvim_win_get_cursor", self.win)?
+            .get::<usize>(1)?)
+    }
+
+    /// Close the window if it is still open.
+    pub fn close(&self) -> nvim_oxi::Result<()> {
+        let api = vim()?.get::<Table>("api")?;
+
+        if api.call_function::<bool>("nvim_win_is_valid", self.win)? {
+            api.call_function::<()>("nvim_win_close", (self.win, true))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+//! Health check for the config, shown under `:checkhealth crabbyvim`.
+//!
+//! The check look for every external executable the config rely on in `$PATH`: the LSP servers
+//! configured with [`crate::lsp::Lsp`], the formatters given to `conform.nvim` and a few other
+//! tools like `git` and `delta`. The servers started by a function instead of a command are
+//! skipped.
+
+use std::path::PathBuf;
+
+use mlua::{ObjectLike, Table};
+
+use crate::lsp::Lsp;
+use crate::{require, table, vim};
+
+/// Other tools used by the config that are not LSP servers or formatters.
+const TOOLS: [&str; 4] = ["git", "lazygit", "delta", "cargo"];
+
+pub(crate) fn configure(lsp: &Lsp) -> nvim_oxi::Result<()> {
+    let servers = lsp
+        .configs()
+        .iter()
+        .map(|config| config.name)
+        .collect::<Vec<_>>();
+
+    vim()?
+        .get::<Table>("package")?
+        .get::<Table>("loaded")?
+        .set(
+            "crabbyvim.health",
+            table! {
+                check = nvim_oxi::mlua::lua().create_function(move |_, ()| {
+                    // the failure is reported under the section that was being checked
+                    if let Err(err) = check(&servers) {
+                        vim()
+                            .map_err(mlua::Error::external)?
+                            .get::<Table>("health")?
+                            .call_function::<()>("error", format!("Health check failed: {err}"))?;
+                    }
+                    Ok(())
+                })?
+            },
+        )?;
+
+    Ok(())
+}
+
+fn check(servers: &[&'static str]) -> nvim_oxi::Result<()> {
+    let health = vim()?.get::<Table>("health")?;
+
+    health.call_function::<()>("start", "LSP servers")?;
+    for server in servers {
+        match server_cmd(server)? {
+            Some(cmd) => report_executable(&health, &cmd, server)?,
+            None => health.call_function::<()>(
+                "info",
+                format!("`{server}` skipped, its command can't be resolved to an executable"),
+            )?,
+        }
+    }
+
+    health.call_function::<()>("start", "Formatters")?;
+    let conform = require("conform")?;
+    let mut formatters = vec![];
+    for pair in conform
+        .get::<Table>("formatters_by_ft")?
+        .pairs::<String, mlua::Value>()
+    {
+        let mlua::Value::Table(list) = pair?.1 else {
+            continue;
+        };
+        for formatter in list.sequence_values::<mlua::Value>() {
+            if let mlua::Value::String(formatter) = formatter? {
+                formatters.push(formatter.to_str()?.to_string());
+            }
+        }
+    }
+    formatters.sort();
+    formatters.dedup();
+
+    for formatter in formatters {
+        let command = conform
+            .call_function::<Table>("get_formatter_info", formatter.as_str())?
+            .get::<Option<String>>("command")?
+            .unwrap_or_else(|| formatter.clone());
+        report_executable(&health, &command, &formatter)?;
+    }
+
+    health.call_function::<()>("start", "Tools")?;
+    for tool in TOOLS {
+        report_executable(&health, tool, tool)?;
+    }
+
+    Ok(())
+}
+
+/// Report if the `executable` needed by `user` (a server, formatter or tool name) is found.
+fn report_executable(health: &Table, executable: &str, user: &str) -> nvim_oxi::Result<()> {
+    let label = if executable == user {
+        format!("`{executable}`")
+    } else {
+        format!("`{executable}` ({user})")
+    };
+
+    match find_executable(executable) {
+        Some(path) => {
+            health.call_function::<()>("ok", format!("{label} found at {}", path.display()))?
+        }
+        None => health.call_function::<()>(
+            "error",
+            (
+                format!("{label} not found in $PATH"),
+                vec![format!("Install `{executable}` or add it to your $PATH")],
+            ),
+        )?,
+    }
+
+    Ok(())
+}
+
+/// Resolve the executable that is run to start the LSP server `name`, `None` if the command isn't a
+/// list like when it is a function.
+fn server_cmd(name: &'static str) -> nvim_oxi::Result<Option<String>> {
+    let config = vim()?
+        .get::<Table>("lsp")?
+        .get::<Table>("config")?
+        .get::<Option<Table>>(name)?;
+
+    Ok(match config {
+        Some(config) => match config.get::<mlua::Value>("cmd")? {
+            mlua::Value::Table(cmd) => cmd.get::<Option<String>>(1)?,
+            _ => None,
+        },
+        None => None,
+    })
+}
+
+/// Search `$PATH` for the given `executable`. On Windows the extension in `PATHEXT` are also
+/// tried.
+#[must_use]
+pub fn find_executable(executable: &str) -> Option<PathBuf> {
+    let extensions = if cfg!(windows) {
+        std::env::var("PATHEXT")
+            .unwrap_or_else(|_| ".EXE;.CMD;.BAT".into())
+            .split(';')
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>()
+    } else {
+        vec![]
+    };
+
+    std::env::split_paths(&std::env::var_os("PATH")?).find_map(|dir| {
+        let path = dir.join(executable);
+        if path.is_file() {
+            return Some(path);
+        }
+        extensions
+            .iter()
+            .map(|ext| dir.join(format!("{executable}{ext}")))
+            .find(|path| path.is_file())
+    })
+}
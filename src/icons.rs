@@ -66,8 +66,20 @@ icon_table! {
 
     LSP = "";
     FORMATTER = "";
+    INLAY_HINT = "";
+    LIGHTBULB = "󰌵";
+    MODIFIED = "●";
+    OVERFLOW_LEFT = "";
+    OVERFLOW_RIGHT = "";
+    TAB = "󰓩";
+    RECORDING = "󰑊";
+    SELECTION = "󰒉";
+    BREADCRUMB = "›";
     GIT_BRANCH = "";
     GIT_DIFF = "";
+    GIT_AHEAD = "⇡";
+    GIT_BEHIND = "⇣";
+    GIT_STASH = "󰀼";
 
     LAZY = "󰒲";
     GEAR = "";
@@ -83,3 +95,40 @@ icon_table! {
     FIVE = "󰬾 ";
     SIX = "󰬿 ";
 }
+
+icon_table! {
+    ROUNDED_LEFT = "";
+    ROUNDED_RIGHT = "";
+    POWERLINE_LEFT = "";
+    POWERLINE_RIGHT = "";
+    SLANT_LEFT = "";
+    SLANT_RIGHT = "";
+    BLOCK_LEFT = "▐";
+    BLOCK_RIGHT = "▌";
+}
+
+/// Return the icon for a LSP `SymbolKind`.
+#[must_use]
+pub fn symbol_kind(kind: i64) -> &'static str {
+    match kind {
+        1 => FILE,
+        2..=4 => MODULE,
+        5 => CLASS,
+        6 => METHOD,
+        7 => PROPERTY,
+        8 => FIELD,
+        9 => CONSTRUCTOR,
+        10 => ENUM,
+        11 => INTERFACE,
+        12 => FUNCTION,
+        13 => VARIABLE,
+        14 => CONSTANT,
+        15 => TEXT,
+        22 => ENUM_MEMBER,
+        23 => STRUCT,
+        24 => EVENT,
+        25 => OPERATOR,
+        26 => TYPE_PARAMETER,
+        _ => VALUE,
+    }
+}
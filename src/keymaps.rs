@@ -1,8 +1,8 @@
 //! Configure keymap and export helper to set new keymap easier.
 
-use mlua::IntoLua;
+use mlua::{IntoLua, ObjectLike, Table};
 
-use crate::Mode;
+use crate::{Mode, table};
 
 #[rustfmt::skip]
 pub(crate) fn configure() -> nvim_oxi::Result<()> {
@@ -121,6 +121,45 @@ pub fn set_key_desc(
     Ok(())
 }
 
+/// Helper to create a normal mode keymap local to the buffer `buf` that call `callback`. Errors
+/// returned by `callback` are written to the message area.
+///
+/// # Examples
+/// ```rust
+/// set_buf_key(buf, "q", "Close", move || float.close())?;
+/// ```
+pub fn set_buf_key<F>(
+    buf: i64,
+    key: &'static str,
+    desc: &'static str,
+    mut callback: F,
+) -> nvim_oxi::Result<()>
+where
+    F: FnMut() -> nvim_oxi::Result<()> + 'static,
+{
+    crate::vim()?.get::<Table>("keymap")?.call_function::<()>(
+        "set",
+        (
+            "n",
+            key,
+            nvim_oxi::mlua::lua().create_function_mut(move |_, ()| {
+                if let Err(err) = callback() {
+                    nvim_oxi::api::err_writeln(format!("{err}").as_str());
+                }
+                Ok(())
+            })?,
+            table! {
+                buffer = buf,
+                desc = desc,
+                nowait = true,
+                silent = true
+            },
+        ),
+    )?;
+
+    Ok(())
+}
+
 impl From<&'static str> for Action {
     fn from(val: &'static str) -> Self {
         Action::Map(val)
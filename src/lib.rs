@@ -9,12 +9,15 @@ use nvim_oxi::api::types::Mode as OxiMode;
 pub mod autocmds;
 pub mod commands;
 pub mod diagnostic;
+pub mod float;
+pub mod health;
 pub mod icons;
 pub mod keymaps;
 pub mod lazy;
 pub mod lsp;
 pub mod macros;
 pub mod options;
+pub mod state;
 pub mod status;
 pub mod theme;
 
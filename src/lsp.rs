@@ -32,12 +32,25 @@
 //! lsp.configure()?;
 //! ```
 
+use std::cell::RefCell;
+use std::rc::Rc;
+
 use mlua::ObjectLike;
 use mlua::Table;
+use nvim_oxi::api::types::LogLevel;
 use nvim_oxi::mlua;
 
 use crate::autocmds::create_autocmd;
-use crate::{Mode, lua_table, require, table, vim};
+use crate::{Mode, lua_table, require, table, vim, vim_notify};
+
+mod cursor_hold;
+mod hierarchy;
+mod info;
+mod inlay_hint;
+pub use inlay_hint::*;
+mod project_settings;
+mod rename;
+mod workspace;
 
 pub(crate) fn configure() -> nvim_oxi::Result<()> {
     let mut lsp = Lsp::new();
@@ -87,11 +100,16 @@ pub(crate) fn configure() -> nvim_oxi::Result<()> {
         settings: lua_table! {},
     });
 
+    crate::health::configure(&lsp)?;
+
+    let lsp = Rc::new(RefCell::new(lsp));
+
+    let configure_lsp = lsp.clone();
     create_autocmd(
         &["BufReadPre", "CmdlineEnter"],
         &["*"],
         move |_| -> nvim_oxi::Result<()> {
-            lsp.configure()?;
+            configure_lsp.borrow_mut().configure()?;
             Ok(())
         },
     )?;
@@ -122,12 +140,35 @@ pub(crate) fn configure() -> nvim_oxi::Result<()> {
         )?;
 
         set_key("<leader>c", "code_action", table! {})?;
+
+        inlay_hint::on_attach(&args.buffer)?;
+        workspace::on_attach(&args.buffer)?;
         Ok(())
     })?;
 
+    inlay_hint::configure()?;
+    workspace::configure()?;
+    cursor_hold::configure()?;
+    hierarchy::configure()?;
+    rename::configure()?;
+    project_settings::configure(lsp.clone())?;
+    info::configure(lsp)?;
+
     Ok(())
 }
 
+/// The clients attached to `buffer` that support `method`, or every attached client when `method`
+/// is `None`. The current buffer is `0`.
+pub(crate) fn buffer_clients(buffer: i64, method: Option<&str>) -> nvim_oxi::Result<Vec<Table>> {
+    Ok(vim()?.get::<Table>("lsp")?.call_function::<Vec<Table>>(
+        "get_clients",
+        table! {
+            bufnr = buffer,
+            method = method
+        },
+    )?)
+}
+
 /// A struct to configure an LSP.
 pub struct LspConfig {
     /// Name of the LSP.
@@ -184,8 +225,23 @@ impl Lsp {
         self.0.push(config);
     }
 
+    /// All the LSP server config added to this structure.
+    #[must_use]
+    pub fn configs(&self) -> &[LspConfig] {
+        &self.0
+    }
+
+    /// Check if [`Lsp::configure`] has already enabled the LSP servers.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.1
+    }
+
     /// Configure the LSP server with all config.
-    pub fn configure(&self) -> nvim_oxi::Result<()> {
+    ///
+    /// The settings found in the project root of each client are merged over the config settings.
+    /// This only configure the servers once, calling this again does nothing.
+    pub fn configure(&mut self) -> nvim_oxi::Result<()> {
         if self.1 {
             return Ok(());
         }
@@ -193,14 +249,23 @@ impl Lsp {
         let lsp_config = vim_lsp.get::<Table>("config")?;
 
         for config in &self.0 {
+            let defaults = config.settings.clone();
             lsp_config.set(config.name, table! {
                 settings = config.settings.clone(),
-                capabilities = require("blink.cmp")?.get::<mlua::Function>("get_lsp_capabilities")?.call::<Table>(())?
+                capabilities = require("blink.cmp")?.get::<mlua::Function>("get_lsp_capabilities")?.call::<Table>(())?,
+                on_init = mlua::lua().create_function(move |_, client: Table| {
+                    if let Err(err) = project_settings::apply(&client, &defaults) {
+                        vim_notify(&format!("Can't apply the project settings: {err}"), LogLevel::Warn)
+                            .map_err(mlua::Error::external)?;
+                    }
+                    Ok(())
+                })?
             })?;
             vim_lsp
                 .get::<mlua::Function>("enable")?
                 .call::<()>(config.name)?;
         }
+        self.1 = true;
 
         Ok(())
     }
@@ -0,0 +1,212 @@
+//! On `CursorHold`, show a lightbulb when code actions are available on the cursor line and
+//! highlight the other references of the symbol under the cursor.
+//!
+//! Both requests are throttled per buffer, skipped while the code actions of the buffer are still
+//! pending and only sent to the clients that support them.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use mlua::{ObjectLike, Table};
+
+use super::buffer_clients;
+use crate::autocmds::create_autocmd;
+use crate::theme::{HighlightOpt, configure_highlights};
+use crate::{icons, table, vim};
+
+/// Minimum time between two requests for the same buffer.
+const THROTTLE: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+struct HoldState {
+    /// When the last requests were sent for each buffer.
+    last_request: HashMap<i32, Instant>,
+    /// Buffers with a pending `textDocument/codeAction` request.
+    pending: HashSet<i32>,
+}
+
+pub(crate) fn configure() -> nvim_oxi::Result<()> {
+    use crate::theme::Color::*;
+
+    configure_highlights(vec![
+        ("LspLightBulb", HighlightOpt::with_fg(Yellow)),
+        ("LspReferenceText", HighlightOpt::with_bg(Bg2)),
+        ("LspReferenceRead", HighlightOpt::with_bg(Bg2)),
+        ("LspReferenceWrite", HighlightOpt::with_bg(Bg2).underline()),
+    ])?;
+
+    let namespace = vim()?
+        .get::<Table>("api")?
+        .call_function::<i64>("nvim_create_namespace", "crabbyvim_lightbulb")?;
+    let state = Rc::new(RefCell::new(HoldState::default()));
+
+    let hold_state = state.clone();
+    create_autocmd(&["CursorHold"], &["*"], move |args| {
+        let buffer = args.buffer.handle();
+
+        {
+            let mut state = hold_state.borrow_mut();
+            let throttled = state
+                .last_request
+                .get(&buffer)
+                .is_some_and(|last| last.elapsed() < THROTTLE);
+            if throttled || state.pending.contains(&buffer) {
+                return Ok(());
+            }
+            state.last_request.insert(buffer, Instant::now());
+        }
+
+        if supported(buffer, "textDocument/codeAction")? {
+            request_code_actions(&hold_state, buffer, cursor()?.0, namespace)?;
+        }
+        if supported(buffer, "textDocument/documentHighlight")? {
+            lsp_buf()?.call_function::<()>("document_highlight", ())?;
+        }
+
+        Ok(())
+    })?;
+
+    create_autocmd(
+        &["CursorMoved", "InsertEnter", "BufLeave"],
+        &["*"],
+        move |args| {
+            vim()?.get::<Table>("api")?.call_function::<()>(
+                "nvim_buf_clear_namespace",
+                (args.buffer.handle(), namespace, 0, -1),
+            )?;
+            lsp_buf()?.call_function::<()>("clear_references", ())?;
+            Ok(())
+        },
+    )?;
+
+    create_autocmd(&["BufDelete"], &["*"], move |args| {
+        let buffer = args.buffer.handle();
+        let mut state = state.borrow_mut();
+        state.last_request.remove(&buffer);
+        state.pending.remove(&buffer);
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+fn request_code_actions(
+    state: &Rc<RefCell<HoldState>>,
+    buffer: i32,
+    row: i64,
+    namespace: i64,
+) -> nvim_oxi::Result<()> {
+    let lua = nvim_oxi::mlua::lua();
+    let handler_state = state.clone();
+
+    vim()?.get::<Table>("lsp")?.call_function::<mlua::Value>(
+        "buf_request_all",
+        (
+            buffer,
+            "textDocument/codeAction",
+            lua.create_function(move |_, (client, _): (Table, i64)| {
+                code_action_params(&client, buffer, row).map_err(mlua::Error::external)
+            })?,
+            lua.create_function(move |_, results: Table| {
+                handler_state.borrow_mut().pending.remove(&buffer);
+                show_lightbulb(&results, buffer, row, namespace).map_err(mlua::Error::external)
+            })?,
+        ),
+    )?;
+    // the handler run once every client answered
+    state.borrow_mut().pending.insert(buffer);
+
+    Ok(())
+}
+
+fn code_action_params(client: &Table, buffer: i32, row: i64) -> nvim_oxi::Result<Table> {
+    let params = vim()?
+        .get::<Table>("lsp")?
+        .get::<Table>("util")?
+        .call_function::<Table>(
+            "make_range_params",
+            (0, client.get::<String>("offset_encoding")?),
+        )?;
+
+    // pass the diagnostics of the line so quick fixes are also returned
+    let diagnostics = table! {};
+    for diagnostic in vim()?
+        .get::<Table>("diagnostic")?
+        .call_function::<Table>("get", (buffer, table! { lnum = row }))?
+        .sequence_values::<Table>()
+    {
+        let lsp = diagnostic?
+            .get::<Option<Table>>("user_data")?
+            .map(|user_data| user_data.get::<Option<Table>>("lsp"))
+            .transpose()?
+            .flatten();
+        if let Some(lsp) = lsp {
+            diagnostics.push(lsp)?;
+        }
+    }
+
+    params.set(
+        "context",
+        table! {
+            diagnostics = diagnostics,
+            triggerKind = 2
+        },
+    )?;
+
+    Ok(params)
+}
+
+fn show_lightbulb(results: &Table, buffer: i32, row: i64, namespace: i64) -> nvim_oxi::Result<()> {
+    let api = vim()?.get::<Table>("api")?;
+
+    // The cursor moved away while the request was running.
+    if api.call_function::<i32>("nvim_get_current_buf", ())? != buffer || cursor()?.0 != row {
+        return Ok(());
+    }
+
+    let mut has_actions = false;
+    for pair in results.pairs::<i64, Table>() {
+        if let Some(actions) = pair?.1.get::<Option<Table>>("result")? {
+            has_actions |= !actions.is_empty();
+        }
+    }
+
+    api.call_function::<()>("nvim_buf_clear_namespace", (buffer, namespace, 0, -1))?;
+
+    if has_actions {
+        api.call_function::<i64>(
+            "nvim_buf_set_extmark",
+            (
+                buffer,
+                namespace,
+                row,
+                0,
+                table! {
+                    virt_text = vec![vec![format!(" {}", icons::LIGHTBULB), "LspLightBulb".into()]],
+                    virt_text_pos = "eol",
+                    hl_mode = "combine"
+                },
+            ),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Return the cursor line starting from 0 and column of the current window.
+fn cursor() -> nvim_oxi::Result<(i64, i64)> {
+    let cursor = vim()?
+        .get::<Table>("api")?
+        .call_function::<Table>("nvim_win_get_cursor", 0)?;
+    Ok((cursor.get::<i64>(1)? - 1, cursor.get::<i64>(2)?))
+}
+
+fn supported(buffer: i32, method: &str) -> nvim_oxi::Result<bool> {
+    Ok(!buffer_clients(buffer.into(), Some(method))?.is_empty())
+}
+
+fn lsp_buf() -> nvim_oxi::Result<Table> {
+    Ok(vim()?.get::<Table>("lsp")?.get::<Table>("buf")?)
+}
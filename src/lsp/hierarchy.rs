@@ -0,0 +1,449 @@
+//! Explore the call and type hierarchy of the symbol under the cursor as a tree in a side buffer.
+//!
+//! Children of a node are only requested when the node is expanded. In the side buffer `<Tab>`
+//! expand or collapse a node, `<CR>` jump to it and `q` close the buffer.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use mlua::{ObjectLike, Table};
+use nvim_oxi::api::types::LogLevel;
+
+use super::buffer_clients;
+use crate::commands::create_command_args;
+use crate::keymaps::{set_buf_key, set_key_desc};
+use crate::{Mode, icons, table, vim, vim_notify};
+
+/// The different hierarchy that can be explored.
+#[derive(Clone, Copy)]
+pub enum Hierarchy {
+    /// Functions calling the symbol.
+    IncomingCalls,
+    /// Functions called by the symbol.
+    OutgoingCalls,
+    /// Parent types of the symbol.
+    Supertypes,
+    /// Child types of the symbol.
+    Subtypes,
+}
+
+impl Hierarchy {
+    fn from_arg(arg: &str) -> Option<Self> {
+        Some(match arg {
+            "incoming" => Self::IncomingCalls,
+            "outgoing" => Self::OutgoingCalls,
+            "supertypes" => Self::Supertypes,
+            "subtypes" => Self::Subtypes,
+            _ => return None,
+        })
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Self::IncomingCalls => "Incoming calls",
+            Self::OutgoingCalls => "Outgoing calls",
+            Self::Supertypes => "Supertypes",
+            Self::Subtypes => "Subtypes",
+        }
+    }
+
+    /// The method to get the root items of the hierarchy.
+    fn prepare_method(self) -> &'static str {
+        match self {
+            Self::IncomingCalls | Self::OutgoingCalls => "textDocument/prepareCallHierarchy",
+            Self::Supertypes | Self::Subtypes => "textDocument/prepareTypeHierarchy",
+        }
+    }
+
+    /// The method to get the children of an item.
+    fn children_method(self) -> &'static str {
+        match self {
+            Self::IncomingCalls => "callHierarchy/incomingCalls",
+            Self::OutgoingCalls => "callHierarchy/outgoingCalls",
+            Self::Supertypes => "typeHierarchy/supertypes",
+            Self::Subtypes => "typeHierarchy/subtypes",
+        }
+    }
+
+    /// Extract the item from an element of the children response.
+    fn child_item(self, child: Table) -> nvim_oxi::Result<Table> {
+        Ok(match self {
+            Self::IncomingCalls => child.get::<Table>("from")?,
+            Self::OutgoingCalls => child.get::<Table>("to")?,
+            Self::Supertypes | Self::Subtypes => child,
+        })
+    }
+}
+
+struct Node {
+    item: Table,
+    /// `None` until the children are requested.
+    children: Option<Vec<Node>>,
+    expanded: bool,
+}
+
+impl Node {
+    fn new(item: Table) -> Self {
+        Self {
+            item,
+            children: None,
+            expanded: false,
+        }
+    }
+}
+
+struct Viewer {
+    hierarchy: Hierarchy,
+    client_id: i64,
+    buf: i64,
+    /// Window the viewer was opened from, where the nodes are opened.
+    source_win: i64,
+    roots: Vec<Node>,
+    /// Path to the node of each rendered line.
+    lines: Vec<Vec<usize>>,
+}
+
+pub(crate) fn configure() -> nvim_oxi::Result<()> {
+    create_command_args(
+        "Show the call or type hierarchy of the symbol under the cursor",
+        "CrabbyHierarchy",
+        |_, _| {
+            vec![
+                "incoming".into(),
+                "outgoing".into(),
+                "supertypes".into(),
+                "subtypes".into(),
+            ]
+        },
+        |args| {
+            let arg = args.first().map_or("incoming", String::as_str);
+            let Some(hierarchy) = Hierarchy::from_arg(arg) else {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "Unknown argument `{arg}`, expected `incoming`, `outgoing`, `supertypes` or \
+                     `subtypes`"
+                ))
+                .into());
+            };
+            show_hierarchy(hierarchy)
+        },
+    )?;
+
+    #[rustfmt::skip]
+    {
+        set_key_desc("Show incoming calls", &[Mode::Normal], "<Leader>ti", "<cmd>CrabbyHierarchy incoming<cr>")?;
+        set_key_desc("Show outgoing calls", &[Mode::Normal], "<Leader>to", "<cmd>CrabbyHierarchy outgoing<cr>")?;
+        set_key_desc("Show supertypes", &[Mode::Normal], "<Leader>tp", "<cmd>CrabbyHierarchy supertypes<cr>")?;
+        set_key_desc("Show subtypes", &[Mode::Normal], "<Leader>tc", "<cmd>CrabbyHierarchy subtypes<cr>")?;
+    }
+
+    Ok(())
+}
+
+/// Request the `hierarchy` of the symbol under the cursor and show it in a side buffer.
+pub fn show_hierarchy(hierarchy: Hierarchy) -> nvim_oxi::Result<()> {
+    let lsp = vim()?.get::<Table>("lsp")?;
+
+    let Some(client) = buffer_clients(0, Some(hierarchy.prepare_method()))?
+        .into_iter()
+        .next()
+    else {
+        return Err(mlua::Error::RuntimeError(format!(
+            "No LSP client support `{}`",
+            hierarchy.prepare_method()
+        ))
+        .into());
+    };
+
+    let client_id = client.get::<i64>("id")?;
+    let params = lsp.get::<Table>("util")?.call_function::<Table>(
+        "make_position_params",
+        (0, client.get::<String>("offset_encoding")?),
+    )?;
+
+    client.call_method::<mlua::Value>(
+        "request",
+        (
+            hierarchy.prepare_method(),
+            params,
+            nvim_oxi::mlua::lua().create_function(
+                move |_, (err, result): (Option<Table>, Option<Vec<Table>>)| {
+                    if let Some(err) = err {
+                        return notify_error(&err);
+                    }
+
+                    let roots = result.unwrap_or_default();
+                    if roots.is_empty() {
+                        nvim_oxi::api::err_writeln("No symbol found under the cursor");
+                        return Ok(());
+                    }
+                    open(hierarchy, client_id, roots).map_err(mlua::Error::external)
+                },
+            )?,
+            0,
+        ),
+    )?;
+
+    Ok(())
+}
+
+/// Show the `ResponseError` of a hierarchy request.
+fn notify_error(err: &Table) -> mlua::Result<()> {
+    vim_notify(
+        &format!(
+            "LSP hierarchy request failed: {}",
+            err.get::<Option<String>>("message")?.unwrap_or_default()
+        ),
+        LogLevel::Error,
+    )
+    .map_err(mlua::Error::external)
+}
+
+/// Open the side buffer with `roots` as the top level node, the first root is expanded.
+fn open(hierarchy: Hierarchy, client_id: i64, roots: Vec<Table>) -> nvim_oxi::Result<()> {
+    let api = vim()?.get::<Table>("api")?;
+    let source_win = api.call_function::<i64>("nvim_get_current_win", ())?;
+
+    nvim_oxi::api::command("botright 50vsplit")?;
+    let win = api.call_function::<i64>("nvim_get_current_win", ())?;
+    let buf = api.call_function::<i64>("nvim_create_buf", (false, true))?;
+    api.call_function::<()>("nvim_win_set_buf", (win, buf))?;
+
+    let wo = vim()?.get::<Table>("wo")?.get::<Table>(win)?;
+    wo.set("number", false)?;
+    wo.set("relativenumber", false)?;
+    wo.set("signcolumn", "no")?;
+    wo.set("wrap", false)?;
+    wo.set("winfixwidth", true)?;
+
+    let bo = vim()?.get::<Table>("bo")?.get::<Table>(buf)?;
+    bo.set("bufhidden", "wipe")?;
+    bo.set("modifiable", false)?;
+    bo.set("filetype", "crabbyvim_hierarchy")?;
+    api.call_function::<()>(
+        "nvim_buf_set_name",
+        (buf, format!("{} {buf}", hierarchy.title())),
+    )?;
+
+    let viewer = Rc::new(RefCell::new(Viewer {
+        hierarchy,
+        client_id,
+        buf,
+        source_win,
+        roots: roots.into_iter().map(Node::new).collect(),
+        lines: vec![],
+    }));
+
+    let toggle_viewer = viewer.clone();
+    set_buf_key(buf, "<Tab>", "Expand or collapse the node", move || {
+        toggle(&toggle_viewer)
+    })?;
+
+    let jump_viewer = viewer.clone();
+    set_buf_key(buf, "<CR>", "Jump to the node", move || jump(&jump_viewer))?;
+
+    set_buf_key(buf, "q", "Close", || Ok(nvim_oxi::api::command("close")?))?;
+
+    render(&viewer)?;
+    expand(&viewer, vec![0])
+}
+
+/// Expand or collapse the node under the cursor.
+fn toggle(viewer: &Rc<RefCell<Viewer>>) -> nvim_oxi::Result<()> {
+    let Some(path) = path_at_cursor(viewer)? else {
+        return Ok(());
+    };
+
+    let loaded = {
+        let mut viewer = viewer.borrow_mut();
+        match node_mut(&mut viewer.roots, &path) {
+            Some(node) if node.children.is_some() => {
+                node.expanded = !node.expanded;
+                true
+            }
+            _ => false,
+        }
+    };
+
+    if loaded {
+        render(viewer)
+    } else {
+        expand(viewer, path)
+    }
+}
+
+/// Request the children of the node at `path` then expand it.
+fn expand(viewer: &Rc<RefCell<Viewer>>, path: Vec<usize>) -> nvim_oxi::Result<()> {
+    let (hierarchy, client_id, item) = {
+        let mut state = viewer.borrow_mut();
+        let (hierarchy, client_id) = (state.hierarchy, state.client_id);
+        let Some(node) = node_mut(&mut state.roots, &path) else {
+            return Ok(());
+        };
+        (hierarchy, client_id, node.item.clone())
+    };
+
+    let Some(client) = vim()?
+        .get::<Table>("lsp")?
+        .call_function::<Option<Table>>("get_client_by_id", client_id)?
+    else {
+        return Err(mlua::Error::RuntimeError("The LSP client has stopped".into()).into());
+    };
+
+    let viewer = viewer.clone();
+    client.call_method::<mlua::Value>(
+        "request",
+        (
+            hierarchy.children_method(),
+            table! { item = item },
+            nvim_oxi::mlua::lua().create_function(
+                move |_, (err, result): (Option<Table>, Option<Vec<Table>>)| {
+                    if let Some(err) = err {
+                        return notify_error(&err);
+                    }
+
+                    let children = result
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|child| hierarchy.child_item(child).map(Node::new))
+                        .collect::<nvim_oxi::Result<Vec<_>>>()
+                        .map_err(mlua::Error::external)?;
+
+                    if let Some(node) = node_mut(&mut viewer.borrow_mut().roots, &path) {
+                        node.children = Some(children);
+                        node.expanded = true;
+                    }
+
+                    render(&viewer).map_err(mlua::Error::external)
+                },
+            )?,
+        ),
+    )?;
+
+    Ok(())
+}
+
+/// Jump to the node under the cursor in the window the viewer was opened from, or in a new split
+/// if that window was closed.
+fn jump(viewer: &Rc<RefCell<Viewer>>) -> nvim_oxi::Result<()> {
+    let Some(path) = path_at_cursor(viewer)? else {
+        return Ok(());
+    };
+
+    let (item, client_id, source_win) = {
+        let mut viewer = viewer.borrow_mut();
+        let (client_id, source_win) = (viewer.client_id, viewer.source_win);
+        match node_mut(&mut viewer.roots, &path) {
+            Some(node) => (node.item.clone(), client_id, source_win),
+            None => return Ok(()),
+        }
+    };
+
+    let lsp = vim()?.get::<Table>("lsp")?;
+    let encoding = lsp
+        .call_function::<Option<Table>>("get_client_by_id", client_id)?
+        .map_or(Ok("utf-16".to_string()), |client| {
+            client.get::<String>("offset_encoding")
+        })?;
+
+    let api = vim()?.get::<Table>("api")?;
+    if api.call_function::<bool>("nvim_win_is_valid", source_win)? {
+        api.call_function::<()>("nvim_set_current_win", source_win)?;
+    } else {
+        nvim_oxi::api::command("topleft vsplit")?;
+        viewer.borrow_mut().source_win = api.call_function::<i64>("nvim_get_current_win", ())?;
+    }
+    lsp.get::<Table>("util")?.call_function::<bool>(
+        "show_document",
+        (
+            table! {
+                uri = item.get::<String>("uri")?,
+                range = item.get::<Table>("selectionRange")?
+            },
+            encoding,
+            table! { focus = true },
+        ),
+    )?;
+
+    Ok(())
+}
+
+/// Render the tree into the side buffer.
+fn render(viewer: &Rc<RefCell<Viewer>>) -> nvim_oxi::Result<()> {
+    fn render_nodes(
+        nodes: &[Node],
+        path: &mut Vec<usize>,
+        lines: &mut Vec<String>,
+        paths: &mut Vec<Vec<usize>>,
+    ) -> nvim_oxi::Result<()> {
+        for (i, node) in nodes.iter().enumerate() {
+            path.push(i);
+
+            let marker = match &node.children {
+                Some(children) if children.is_empty() => " ",
+                Some(_) if node.expanded => "▾",
+                _ => "▸",
+            };
+            let detail = node
+                .item
+                .get::<Option<String>>("detail")?
+                .unwrap_or_default();
+
+            lines.push(
+                format!(
+                    "{}{marker} {} {} {detail}",
+                    "  ".repeat(path.len() - 1),
+                    icons::symbol_kind(node.item.get::<i64>("kind")?),
+                    node.item.get::<String>("name")?,
+                )
+                .trim_end()
+                .to_string(),
+            );
+            paths.push(path.clone());
+
+            if let Some(children) = &node.children
+                && node.expanded
+            {
+                render_nodes(children, path, lines, paths)?;
+            }
+
+            path.pop();
+        }
+        Ok(())
+    }
+
+    let mut viewer = viewer.borrow_mut();
+    let (mut lines, mut paths) = (vec![], vec![]);
+    render_nodes(&viewer.roots, &mut vec![], &mut lines, &mut paths)?;
+    viewer.lines = paths;
+
+    let api = vim()?.get::<Table>("api")?;
+    if !api.call_function::<bool>("nvim_buf_is_valid", viewer.buf)? {
+        return Ok(());
+    }
+
+    let bo = vim()?.get::<Table>("bo")?.get::<Table>(viewer.buf)?;
+    bo.set("modifiable", true)?;
+    api.call_function::<()>("nvim_buf_set_lines", (viewer.buf, 0, -1, false, lines))?;
+    bo.set("modifiable", false)?;
+
+    Ok(())
+}
+
+fn path_at_cursor(viewer: &Rc<RefCell<Viewer>>) -> nvim_oxi::Result<Option<Vec<usize>>> {
+    let line = vim()?
+        .get::<Table>("api")?
+        .call_function::<Table>("nvim_win_get_cursor", 0)?
+        .get::<usize>(1)?;
+
+    Ok(viewer.borrow().lines.get(line - 1).cloned())
+}
+
+fn node_mut<'a>(nodes: &'a mut [Node], path: &[usize]) -> Option<&'a mut Node> {
+    let (first, rest) = path.split_first()?;
+    let node = nodes.get_mut(*first)?;
+    if rest.is_empty() {
+        Some(node)
+    } else {
+        node_mut(node.children.as_mut()?, rest)
+    }
+}
@@ -0,0 +1,221 @@
+//! `:CrabbyLspInfo`, a report of every configured LSP server and their running clients.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use mlua::{ObjectLike, Table};
+use nvim_oxi::api::types::CommandArgs;
+
+use crate::commands::create_command;
+use crate::float::Float;
+use crate::{expr, table, vim, vim_fn};
+
+use super::Lsp;
+
+/// How often a restarted client is checked for exit in milliseconds.
+const RESTART_POLL_INTERVAL: i64 = 100;
+
+pub(crate) fn configure(lsp: Rc<RefCell<Lsp>>) -> nvim_oxi::Result<()> {
+    create_command(
+        "Show the configured LSP servers and their clients",
+        "CrabbyLspInfo",
+        move |_: CommandArgs| open(&lsp),
+    )?;
+
+    Ok(())
+}
+
+/// Open the report in a floating window along with keymaps to manage the client under the cursor.
+fn open(lsp: &Rc<RefCell<Lsp>>) -> nvim_oxi::Result<()> {
+    let (lines, clients) = report(&lsp.borrow())?;
+    let float = Float::open("LSP Info", &lines)?;
+    let clients = Rc::new(RefCell::new(clients));
+
+    // Find the client of the section under the cursor, `None` on a server without client.
+    let client_at_cursor = {
+        let clients = clients.clone();
+        move || -> nvim_oxi::Result<Option<Table>> {
+            let line = float.cursor_line()?;
+            let Some((_, Some(id))) = clients
+                .borrow()
+                .iter()
+                .rev()
+                .find(|(section_line, _)| *section_line <= line)
+                .copied()
+            else {
+                return Ok(None);
+            };
+
+            Ok(vim()?
+                .get::<Table>("lsp")?
+                .call_function::<Option<Table>>("get_client_by_id", id)?)
+        }
+    };
+
+    let refresh = {
+        let lsp = lsp.clone();
+        move || -> nvim_oxi::Result<()> {
+            let (lines, new_clients) = report(&lsp.borrow())?;
+            float.set_lines(&lines)?;
+            *clients.borrow_mut() = new_clients;
+            Ok(())
+        }
+    };
+
+    let (client, refresh_after) = (client_at_cursor.clone(), refresh.clone());
+    float.set_key("r", "Restart the client under the cursor", move || {
+        if let Some(client) = client()? {
+            restart(&client)?;
+        }
+        refresh_after()
+    })?;
+
+    let (client, refresh_after) = (client_at_cursor, refresh.clone());
+    float.set_key("s", "Stop the client under the cursor", move || {
+        if let Some(client) = client()? {
+            client.call_method::<()>("stop", ())?;
+        }
+        refresh_after()
+    })?;
+
+    float.set_key("R", "Refresh the report", refresh)?;
+
+    float.set_key("l", "Open the LSP log", move || {
+        float.close()?;
+        let lsp = vim()?.get::<Table>("lsp")?;
+        vim()?
+            .get::<Table>("cmd")?
+            .call_function::<()>("edit", lsp.call_function::<String>("get_log_path", ())?)?;
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// Stop `client` and start a new client for its buffers once it has exited.
+fn restart(client: &Table) -> nvim_oxi::Result<()> {
+    let name = client.get::<String>("name")?;
+    let buffers = client
+        .get::<Table>("attached_buffers")?
+        .pairs::<i64, bool>()
+        .map(|pair| pair.map(|(buf, _)| buf))
+        .collect::<mlua::Result<Vec<_>>>()?;
+
+    client.call_method::<()>("stop", ())?;
+
+    let client = client.clone();
+    vim_fn::<i64>(
+        "timer_start",
+        (
+            RESTART_POLL_INTERVAL,
+            nvim_oxi::mlua::lua().create_function(move |_, timer: i64| {
+                if !client.call_method::<bool>("is_stopped", ())? {
+                    return Ok(());
+                }
+                vim_fn::<()>("timer_stop", timer).map_err(mlua::Error::external)?;
+
+                let lsp = vim().map_err(mlua::Error::external)?.get::<Table>("lsp")?;
+                let config = lsp.get::<Table>("config")?.get::<Table>(name.as_str())?;
+                let api = vim().map_err(mlua::Error::external)?.get::<Table>("api")?;
+                for &buf in &buffers {
+                    if api.call_function::<bool>("nvim_buf_is_valid", buf)? {
+                        lsp.call_function::<Option<i64>>(
+                            "start",
+                            (config.clone(), table! { bufnr = buf }),
+                        )?;
+                    }
+                }
+                Ok(())
+            })?,
+            table! { ["repeat"] = -1 },
+        ),
+    )?;
+
+    Ok(())
+}
+
+/// Render the report, returning the lines as well as the line (starting from 1) where each server
+/// and client section start along with the client id, `None` for the server sections.
+fn report(lsp: &Lsp) -> nvim_oxi::Result<(Vec<String>, Vec<(usize, Option<i64>)>)> {
+    let vim_lsp = vim()?.get::<Table>("lsp")?;
+    let inspect = expr! {
+        return mlua::Function;
+        function(value) return vim.inspect(value) end
+    };
+
+    let mut lines = vec![
+        "r: restart  s: stop  R: refresh  l: open log  q: close".to_string(),
+        String::new(),
+    ];
+    let mut clients = vec![];
+
+    for config in lsp.configs() {
+        clients.push((lines.len() + 1, None));
+        lines.push(format!(
+            "{} ({})",
+            config.name,
+            if lsp.is_enabled() {
+                "enabled"
+            } else {
+                "not enabled"
+            }
+        ));
+
+        let running =
+            vim_lsp.call_function::<Table>("get_clients", table! { name = config.name })?;
+
+        if running.is_empty() {
+            lines.push("  no client running".into());
+        }
+
+        for client in running.sequence_values::<Table>() {
+            let client = client?;
+            let id = client.get::<i64>("id")?;
+
+            clients.push((lines.len() + 1, Some(id)));
+            lines.push(format!("  client {id}"));
+
+            lines.push(format!(
+                "    root dir: {}",
+                client
+                    .get::<Option<String>>("root_dir")?
+                    .unwrap_or_else(|| "none".into())
+            ));
+
+            let mut buffers = client
+                .get::<Table>("attached_buffers")?
+                .pairs::<i64, bool>()
+                .map(|pair| pair.map(|(buf, _)| buf))
+                .collect::<mlua::Result<Vec<_>>>()?;
+            buffers.sort_unstable();
+            lines.push(format!(
+                "    buffers: {}",
+                buffers
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+
+            let mut capabilities = client
+                .get::<Table>("server_capabilities")?
+                .pairs::<String, mlua::Value>()
+                .filter_map(|pair| match pair {
+                    Ok((_, mlua::Value::Boolean(false) | mlua::Value::Nil)) => None,
+                    Ok((name, _)) => Some(Ok(name)),
+                    Err(err) => Some(Err(err)),
+                })
+                .collect::<mlua::Result<Vec<_>>>()?;
+            capabilities.sort();
+            lines.push(format!("    capabilities: {}", capabilities.join(", ")));
+
+            lines.push("    settings:".into());
+            let settings = inspect.call::<String>(client.get::<mlua::Value>("settings")?)?;
+            lines.extend(settings.lines().map(|line| format!("      {line}")));
+        }
+
+        lines.push(String::new());
+    }
+
+    Ok((lines, clients))
+}
@@ -0,0 +1,96 @@
+//! Toggle LSP inlay hints and remember the preference for each file type across sessions.
+
+use mlua::{ObjectLike, Table};
+use nvim_oxi::api::Buffer;
+
+use super::buffer_clients;
+use crate::commands::create_command_args;
+use crate::keymaps::set_key_desc;
+use crate::state::{load_state, save_state};
+use crate::{Mode, table, vim};
+
+const STATE: &str = "inlay_hints";
+
+pub(crate) fn configure() -> nvim_oxi::Result<()> {
+    create_command_args(
+        "Toggle LSP inlay hints for the current buffer",
+        "CrabbyInlayHints",
+        |_, _| vec!["on".into(), "off".into(), "toggle".into()],
+        |args| {
+            let buffer = Buffer::current();
+            let enable = match args.first().map(String::as_str) {
+                Some("on") => true,
+                Some("off") => false,
+                Some("toggle") | None => !inlay_hints_enabled(&buffer)?,
+                Some(arg) => {
+                    return Err(mlua::Error::RuntimeError(format!(
+                        "Unknown argument `{arg}`, expected `on`, `off` or `toggle`"
+                    ))
+                    .into());
+                }
+            };
+
+            set_inlay_hints(&buffer, enable)?;
+
+            let state = load_state(STATE)?;
+            state.set(filetype(&buffer)?, enable)?;
+            save_state(STATE, &state)
+        },
+    )?;
+
+    set_key_desc(
+        "Toggle inlay hints",
+        &[Mode::Normal],
+        "<Leader>i",
+        "<cmd>CrabbyInlayHints toggle<cr>",
+    )?;
+
+    Ok(())
+}
+
+/// Apply the remembered inlay hints preference of the file type to `buffer`. Only done when one of
+/// the attached client actually support inlay hints.
+pub(crate) fn on_attach(buffer: &Buffer) -> nvim_oxi::Result<()> {
+    let supported =
+        !buffer_clients(buffer.handle().into(), Some("textDocument/inlayHint"))?.is_empty();
+
+    if supported
+        && load_state(STATE)?
+            .get::<Option<bool>>(filetype(buffer)?)?
+            .unwrap_or(false)
+    {
+        set_inlay_hints(buffer, true)?;
+    }
+
+    Ok(())
+}
+
+/// Enable or disable inlay hints for `buffer` then fire the `User CrabbyInlayHints` event.
+pub fn set_inlay_hints(buffer: &Buffer, enable: bool) -> nvim_oxi::Result<()> {
+    vim()?
+        .get::<Table>("lsp")?
+        .get::<Table>("inlay_hint")?
+        .call_function::<()>("enable", (enable, table! { bufnr = buffer.handle() }))?;
+
+    // let the status line know the inlay hints changed
+    vim()?.get::<Table>("api")?.call_function::<()>(
+        "nvim_exec_autocmds",
+        ("User", table! { pattern = "CrabbyInlayHints" }),
+    )?;
+    Ok(())
+}
+
+/// Check if inlay hints are enabled for `buffer`.
+pub fn inlay_hints_enabled(buffer: &Buffer) -> nvim_oxi::Result<bool> {
+    Ok(vim()?
+        .get::<Table>("lsp")?
+        .get::<Table>("inlay_hint")?
+        .call_function::<bool>("is_enabled", table! { bufnr = buffer.handle() })?)
+}
+
+fn filetype(buffer: &Buffer) -> nvim_oxi::Result<String> {
+    Ok(vim()?
+        .get::<Table>("bo")?
+        .get::<Table>(buffer.handle())?
+        .get::<String>("filetype")?)
+}
@@ -0,0 +1,475 @@
+//! Merge the LSP settings found in the project root over the defaults of each [`LspConfig`].
+//!
+//! Settings are read from `.vscode/settings.json`, where only the keys under the prefix of a server
+//! are used (`rust-analyzer.*`, `Lua.*` and `tinymist.*`), and from `rust-analyzer.toml`. Saving
+//! one of these files push the new settings to the running clients.
+//!
+//! [`LspConfig`]: super::LspConfig
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::path::Path;
+use std::rc::Rc;
+use std::str::Chars;
+
+use mlua::{ObjectLike, Table};
+use nvim_oxi::api::types::LogLevel;
+
+use crate::autocmds::create_autocmd;
+use crate::{table, vim, vim_fn, vim_notify};
+
+use super::Lsp;
+
+#[cfg(test)]
+mod tests;
+
+/// The server name, the key prefix in `.vscode/settings.json` and the section the keys are put in
+/// the server settings, `None` put them at the top level.
+const SECTIONS: &[(&str, &str, Option<&str>)] = &[
+    ("rust_analyzer", "rust-analyzer", Some("rust-analyzer")),
+    ("lua_ls", "Lua", Some("Lua")),
+    ("tinymist", "tinymist", None),
+];
+
+pub(crate) fn configure(lsp: Rc<RefCell<Lsp>>) -> nvim_oxi::Result<()> {
+    create_autocmd(
+        &["BufWritePost"],
+        &["*/.vscode/settings.json", "*/rust-analyzer.toml"],
+        move |_| {
+            let clients = vim()?
+                .get::<Table>("lsp")?
+                .call_function::<Table>("get_clients", ())?;
+
+            for client in clients.sequence_values::<Table>() {
+                let client = client?;
+                let name = client.get::<String>("name")?;
+                if let Some(config) = lsp.borrow().configs().iter().find(|c| c.name == name) {
+                    apply(&client, &config.settings)?;
+                }
+            }
+
+            Ok(())
+        },
+    )
+}
+
+/// Merge the project settings of `client` over `defaults` and send them to the server.
+pub(crate) fn apply(client: &Table, defaults: &Table) -> nvim_oxi::Result<()> {
+    let root = match client.get::<Option<String>>("root_dir")? {
+        Some(root) => root,
+        None => vim_fn::<String>("getcwd", ())?,
+    };
+
+    let settings = vim()?.call_function::<Table>(
+        "tbl_deep_extend",
+        (
+            "force",
+            table! {},
+            defaults.clone(),
+            project_settings(&client.get::<String>("name")?, Path::new(&root))?,
+        ),
+    )?;
+
+    client.set("settings", settings.clone())?;
+    client.call_method::<bool>(
+        "notify",
+        (
+            "workspace/didChangeConfiguration",
+            table! { settings = settings },
+        ),
+    )?;
+
+    Ok(())
+}
+
+/// Read the settings for the server `name` from the project files in `root`.
+fn project_settings(name: &str, root: &Path) -> nvim_oxi::Result<Table> {
+    let settings = table! {};
+
+    if let Some((_, prefix, section)) = SECTIONS.iter().find(|(server, ..)| *server == name)
+        && let Ok(content) = std::fs::read_to_string(root.join(".vscode").join("settings.json"))
+    {
+        match vim()?
+            .get::<Table>("json")?
+            .call_function::<Table>("decode", strip_json_comments(&content))
+        {
+            Ok(vscode) => {
+                for pair in vscode.pairs::<String, mlua::Value>() {
+                    let (key, value) = pair?;
+                    let Some(key) = key.strip_prefix(prefix).and_then(|k| k.strip_prefix('.'))
+                    else {
+                        continue;
+                    };
+
+                    let path = section.iter().copied().chain(key.split('.'));
+                    set_path(&settings, &path.collect::<Vec<_>>(), value)?;
+                }
+            }
+            Err(err) => vim_notify(
+                &format!("Can't read `.vscode/settings.json`: {err}"),
+                LogLevel::Warn,
+            )?,
+        }
+    }
+
+    if name == "rust_analyzer"
+        && let Ok(content) = std::fs::read_to_string(root.join("rust-analyzer.toml"))
+    {
+        match parse_toml(&content) {
+            Ok(toml) => set_path(
+                &settings,
+                &["rust-analyzer"],
+                toml_to_lua(TomlValue::Table(toml))?,
+            )?,
+            Err(err) => vim_notify(
+                &format!("Can't read `rust-analyzer.toml`: {err}"),
+                LogLevel::Warn,
+            )?,
+        }
+    }
+
+    Ok(settings)
+}
+
+/// Set `value` at the nested `path` of `table`, tables are deep merged and the missing tables along
+/// the way are created.
+fn set_path(table: &Table, path: &[&str], value: mlua::Value) -> mlua::Result<()> {
+    let Some((last, path)) = path.split_last() else {
+        return Ok(());
+    };
+
+    let mut current = table.clone();
+    for key in path {
+        current = match current.get::<Option<Table>>(*key)? {
+            Some(next) => next,
+            None => {
+                let next = nvim_oxi::mlua::lua().create_table()?;
+                current.set(*key, next.clone())?;
+                next
+            }
+        };
+    }
+
+    match (current.get::<Option<Table>>(*last)?, value) {
+        (Some(existing), mlua::Value::Table(value)) => {
+            for pair in value.pairs::<String, mlua::Value>() {
+                let (key, value) = pair?;
+                set_path(&existing, &[&key], value)?;
+            }
+            Ok(())
+        }
+        (_, value) => current.set(*last, value),
+    }
+}
+
+/// Remove the comments and trailing commas VS Code allow in its json files.
+fn strip_json_comments(content: &str) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            match c {
+                '\\' => output.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                output.push(c);
+            }
+            ('/', Some('/')) => while chars.next_if(|&c| c != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                while let Some(c) = chars.next() {
+                    if c == '*' && chars.next_if_eq(&'/').is_some() {
+                        break;
+                    }
+                }
+            }
+            ('}' | ']', _) => {
+                let trimmed = output.trim_end().len();
+                if output[..trimmed].ends_with(',') {
+                    output.truncate(trimmed - 1);
+                }
+                output.push(c);
+            }
+            _ => output.push(c),
+        }
+    }
+
+    output
+}
+
+/// A value of `rust-analyzer.toml`, parsed without Lua and converted with [`toml_to_lua`].
+#[derive(Debug, PartialEq)]
+enum TomlValue {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Array(Vec<TomlValue>),
+    Table(BTreeMap<String, TomlValue>),
+}
+
+/// Parse the subset of toml used by `rust-analyzer.toml`: tables, inline tables, dotted keys,
+/// strings, numbers, booleans and arrays of them.
+fn parse_toml(content: &str) -> Result<BTreeMap<String, TomlValue>, String> {
+    let mut parser = TomlParser {
+        chars: content.chars().peekable(),
+        line: 1,
+    };
+    parser
+        .document()
+        .map_err(|msg| format!("line {}: {msg}", parser.line))
+}
+
+struct TomlParser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl TomlParser<'_> {
+    fn document(&mut self) -> Result<BTreeMap<String, TomlValue>, String> {
+        let mut root = BTreeMap::new();
+        let mut section = vec![];
+
+        loop {
+            self.skip_blank(true);
+            match self.chars.peek() {
+                None => return Ok(root),
+                Some('[') => {
+                    self.chars.next();
+                    if self.chars.next_if_eq(&'[').is_some() {
+                        return Err("arrays of tables are not supported".into());
+                    }
+                    section = self.key()?;
+                    self.expect(']')?;
+                    insert_toml(&mut root, &section, TomlValue::Table(BTreeMap::new()))?;
+                }
+                Some(_) => {
+                    let mut path = section.clone();
+                    path.extend(self.key()?);
+                    self.expect('=')?;
+                    let value = self.value()?;
+                    insert_toml(&mut root, &path, value)?;
+                }
+            }
+
+            self.skip_blank(false);
+            if self.chars.next_if(|&c| c != '\n').is_some() {
+                return Err("expected a new line".into());
+            }
+        }
+    }
+
+    /// Skip the spaces and comments, and the new lines too when `newlines` is `true`.
+    fn skip_blank(&mut self, newlines: bool) {
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                ' ' | '\t' | '\r' => {
+                    self.chars.next();
+                }
+                '\n' if newlines => {
+                    self.line += 1;
+                    self.chars.next();
+                }
+                '#' => while self.chars.next_if(|&c| c != '\n').is_some() {},
+                _ => return,
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_blank(false);
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => Err(format!("expected `{expected}`")),
+        }
+    }
+
+    /// A dotted key made of bare or quoted parts.
+    fn key(&mut self) -> Result<Vec<String>, String> {
+        let mut key = vec![];
+
+        loop {
+            self.skip_blank(false);
+            let part = if let Some(quote) = self.chars.next_if(|&c| c == '"' || c == '\'') {
+                self.string(quote)?
+            } else {
+                let mut part = String::new();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|&c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                {
+                    part.push(c);
+                }
+                part
+            };
+
+            if part.is_empty() {
+                return Err("expected a key".into());
+            }
+            key.push(part);
+
+            self.skip_blank(false);
+            if self.chars.next_if_eq(&'.').is_none() {
+                return Ok(key);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<TomlValue, String> {
+        self.skip_blank(false);
+
+        match self.chars.peek() {
+            Some(&quote @ ('"' | '\'')) => {
+                self.chars.next();
+                Ok(TomlValue::String(self.string(quote)?))
+            }
+            Some('[') => {
+                self.chars.next();
+                let mut array = vec![];
+                loop {
+                    self.skip_blank(true);
+                    if self.chars.next_if_eq(&']').is_some() {
+                        return Ok(TomlValue::Array(array));
+                    }
+                    array.push(self.value()?);
+                    self.skip_blank(true);
+                    if self.chars.next_if_eq(&',').is_none() {
+                        self.expect(']')?;
+                        return Ok(TomlValue::Array(array));
+                    }
+                }
+            }
+            Some('{') => {
+                self.chars.next();
+                let mut table = BTreeMap::new();
+                self.skip_blank(false);
+                if self.chars.next_if_eq(&'}').is_some() {
+                    return Ok(TomlValue::Table(table));
+                }
+                loop {
+                    let key = self.key()?;
+                    self.expect('=')?;
+                    let value = self.value()?;
+                    insert_toml(&mut table, &key, value)?;
+                    self.skip_blank(false);
+                    if self.chars.next_if_eq(&',').is_none() {
+                        self.expect('}')?;
+                        return Ok(TomlValue::Table(table));
+                    }
+                }
+            }
+            _ => {
+                let mut token = String::new();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|&c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+' | '.'))
+                {
+                    token.push(c);
+                }
+
+                match token.as_str() {
+                    "true" => Ok(TomlValue::Boolean(true)),
+                    "false" => Ok(TomlValue::Boolean(false)),
+                    _ => {
+                        let number = token.replace('_', "");
+                        number
+                            .parse::<i64>()
+                            .map(TomlValue::Integer)
+                            .or_else(|_| number.parse::<f64>().map(TomlValue::Float))
+                            .map_err(|_| "invalid value".into())
+                    }
+                }
+            }
+        }
+    }
+
+    /// The rest of a string opened with `quote`, escapes are only read in double quoted strings.
+    fn string(&mut self, quote: char) -> Result<String, String> {
+        let mut string = String::new();
+
+        loop {
+            match self.chars.next() {
+                None | Some('\n') => return Err("unterminated string".into()),
+                Some(c) if c == quote => return Ok(string),
+                Some('\\') if quote == '"' => match self.chars.next() {
+                    Some('n') => string.push('\n'),
+                    Some('t') => string.push('\t'),
+                    Some(c @ ('"' | '\\')) => string.push(c),
+                    _ => return Err("invalid escape".into()),
+                },
+                Some(c) => string.push(c),
+            }
+        }
+    }
+}
+
+/// Insert `value` at the nested `path` of `table` like [`set_path`] does for Lua tables.
+fn insert_toml(
+    table: &mut BTreeMap<String, TomlValue>,
+    path: &[String],
+    value: TomlValue,
+) -> Result<(), String> {
+    let Some((last, path)) = path.split_last() else {
+        return Ok(());
+    };
+
+    let mut current = table;
+    for key in path {
+        current = match current
+            .entry(key.clone())
+            .or_insert_with(|| TomlValue::Table(BTreeMap::new()))
+        {
+            TomlValue::Table(next) => next,
+            _ => return Err(format!("`{key}` is not a table")),
+        };
+    }
+
+    match (current.get_mut(last), value) {
+        (Some(TomlValue::Table(existing)), TomlValue::Table(value)) => {
+            for (key, value) in value {
+                insert_toml(existing, &[key], value)?;
+            }
+            Ok(())
+        }
+        (_, value) => {
+            current.insert(last.clone(), value);
+            Ok(())
+        }
+    }
+}
+
+fn toml_to_lua(value: TomlValue) -> mlua::Result<mlua::Value> {
+    let lua = nvim_oxi::mlua::lua();
+
+    Ok(match value {
+        TomlValue::String(string) => mlua::Value::String(lua.create_string(string)?),
+        TomlValue::Integer(integer) => mlua::Value::Integer(integer),
+        TomlValue::Float(float) => mlua::Value::Number(float),
+        TomlValue::Boolean(boolean) => mlua::Value::Boolean(boolean),
+        TomlValue::Array(array) => {
+            let table = lua.create_table()?;
+            for item in array {
+                table.push(toml_to_lua(item)?)?;
+            }
+            mlua::Value::Table(table)
+        }
+        TomlValue::Table(map) => {
+            let table = lua.create_table()?;
+            for (key, value) in map {
+                table.set(key, toml_to_lua(value)?)?;
+            }
+            mlua::Value::Table(table)
+        }
+    })
+}
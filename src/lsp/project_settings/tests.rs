@@ -0,0 +1,182 @@
+use super::*;
+
+fn table<const N: usize>(pairs: [(&str, TomlValue); N]) -> TomlValue {
+    TomlValue::Table(pairs.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+}
+
+fn string(value: &str) -> TomlValue {
+    TomlValue::String(value.to_string())
+}
+
+#[test]
+fn json_comments_are_stripped() {
+    let cases = [
+        ("{\"a\": 1 // comment\n}", "{\"a\": 1 \n}"),
+        ("{/* block\n comment */\"a\": 1}", "{\"a\": 1}"),
+        (
+            "{\"url\": \"https://example.com\"}",
+            "{\"url\": \"https://example.com\"}",
+        ),
+        (
+            "{\"a\": \"/* not a comment */\"}",
+            "{\"a\": \"/* not a comment */\"}",
+        ),
+        (
+            "{\"a\": \"escaped \\\" // quote\"}",
+            "{\"a\": \"escaped \\\" // quote\"}",
+        ),
+        ("{\"a\": [1, 2,],\n}", "{\"a\": [1, 2]}"),
+        ("{\"a\": 1, // last\n}", "{\"a\": 1}"),
+        ("{\"a\": 1 /* unterminated", "{\"a\": 1 "),
+    ];
+
+    for (json, expected) in cases {
+        assert_eq!(strip_json_comments(json), expected, "{json:?}");
+    }
+}
+
+#[test]
+fn toml_values() {
+    let toml = parse_toml(
+        r#"
+        string = "a \"quoted\" \\ string" # comment
+        literal = 'C:\path # not a comment'
+        integer = 1_000
+        negative = -2
+        float = 0.5
+        boolean = true
+        "quoted.key" = false
+        "#,
+    );
+
+    assert_eq!(
+        toml.map(TomlValue::Table),
+        Ok(table([
+            ("string", string("a \"quoted\" \\ string")),
+            ("literal", string("C:\\path # not a comment")),
+            ("integer", TomlValue::Integer(1000)),
+            ("negative", TomlValue::Integer(-2)),
+            ("float", TomlValue::Float(0.5)),
+            ("boolean", TomlValue::Boolean(true)),
+            ("quoted.key", TomlValue::Boolean(false)),
+        ]))
+    );
+}
+
+#[test]
+fn toml_nested_tables() {
+    let toml = parse_toml(
+        r#"
+        cargo.features = "all"
+
+        [check]
+        command = "clippy"
+
+        [imports.granularity]
+        group = "module"
+
+        [cargo]
+        buildScripts = { enable = true, rebuildOnSave = false }
+        "#,
+    );
+
+    assert_eq!(
+        toml.map(TomlValue::Table),
+        Ok(table([
+            (
+                "cargo",
+                table([
+                    ("features", string("all")),
+                    (
+                        "buildScripts",
+                        table([
+                            ("enable", TomlValue::Boolean(true)),
+                            ("rebuildOnSave", TomlValue::Boolean(false)),
+                        ])
+                    ),
+                ])
+            ),
+            ("check", table([("command", string("clippy"))])),
+            (
+                "imports",
+                table([("granularity", table([("group", string("module"))]))])
+            ),
+        ]))
+    );
+}
+
+#[test]
+fn toml_arrays() {
+    let toml = parse_toml(
+        r#"
+        empty = []
+        single = ["a, b"]
+        multi = [
+            "--all", # comment
+            'literal',
+            [1, 2],
+        ]
+        tables = [{ a = 1 }, {}]
+        "#,
+    );
+
+    assert_eq!(
+        toml.map(TomlValue::Table),
+        Ok(table([
+            ("empty", TomlValue::Array(vec![])),
+            ("single", TomlValue::Array(vec![string("a, b")])),
+            (
+                "multi",
+                TomlValue::Array(vec![
+                    string("--all"),
+                    string("literal"),
+                    TomlValue::Array(vec![TomlValue::Integer(1), TomlValue::Integer(2)]),
+                ])
+            ),
+            (
+                "tables",
+                TomlValue::Array(vec![table([("a", TomlValue::Integer(1))]), table([]),])
+            ),
+        ]))
+    );
+}
+
+#[test]
+fn malformed_toml() {
+    let cases = [
+        ("key", "line 1: expected `=`"),
+        ("key = ", "line 1: invalid value"),
+        ("key = nope", "line 1: invalid value"),
+        ("\n\nkey = \"unterminated", "line 3: unterminated string"),
+        ("key = [1, 2", "line 1: expected `]`"),
+        ("key = { a = 1", "line 1: expected `}`"),
+        ("[[array]]", "line 1: arrays of tables are not supported"),
+        ("[table", "line 1: expected `]`"),
+        ("a = 1 b = 2", "line 1: expected a new line"),
+        ("a = 1\na.b = 2", "line 2: `a` is not a table"),
+        ("= 1", "line 1: expected a key"),
+        ("key = \"\\x\"", "line 1: invalid escape"),
+    ];
+
+    for (toml, expected) in cases {
+        assert_eq!(parse_toml(toml), Err(expected.to_string()), "{toml:?}");
+    }
+}
+
+#[test]
+fn toml_tables_are_merged() {
+    let mut root = BTreeMap::new();
+    let path = |path: &str| path.split('.').map(String::from).collect::<Vec<_>>();
+
+    insert_toml(&mut root, &path("a.b"), TomlValue::Integer(1)).unwrap();
+    insert_toml(&mut root, &path("a"), table([("c", TomlValue::Integer(2))])).unwrap();
+    insert_toml(&mut root, &path("a.b"), TomlValue::Integer(3)).unwrap();
+
+    assert_eq!(
+        TomlValue::Table(root),
+        table([(
+            "a",
+            table([("b", TomlValue::Integer(3)), ("c", TomlValue::Integer(2))])
+        )])
+    );
+}
@@ -0,0 +1,349 @@
+//! Rename the symbol under the cursor with a preview of every edit before they are applied.
+//!
+//! The preview list the edits grouped by file, `<Tab>` accept or reject the file or edit under the
+//! cursor, `<CR>` apply the accepted edits and `q` cancel the rename. The accepted edits of a buffer
+//! are applied at once so the rename can be undone with a single undo.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use mlua::{ObjectLike, Table};
+use nvim_oxi::api::types::LogLevel;
+
+use super::buffer_clients;
+use crate::commands::create_command_args;
+use crate::float::Float;
+use crate::keymaps::set_key_desc;
+use crate::{Mode, table, vim, vim_fn, vim_notify};
+
+struct Hunk {
+    edit: Table,
+    /// Line of the edit, starting from 0.
+    line: i64,
+    before: String,
+    after: String,
+    accepted: bool,
+}
+
+struct FileEdit {
+    uri: String,
+    path: String,
+    hunks: Vec<Hunk>,
+}
+
+impl FileEdit {
+    fn checkbox(&self) -> &'static str {
+        let accepted = self.hunks.iter().filter(|hunk| hunk.accepted).count();
+        if accepted == self.hunks.len() {
+            "[x]"
+        } else if accepted == 0 {
+            "[ ]"
+        } else {
+            "[-]"
+        }
+    }
+}
+
+struct Preview {
+    offset_encoding: String,
+    files: Vec<FileEdit>,
+    /// The file and hunk of each rendered line.
+    lines: Vec<Option<(usize, Option<usize>)>>,
+}
+
+pub(crate) fn configure() -> nvim_oxi::Result<()> {
+    create_command_args(
+        "Rename the symbol under the cursor with a preview",
+        "CrabbyRename",
+        |_, _| vec![],
+        |args| rename(args.first().cloned()),
+    )?;
+
+    set_key_desc(
+        "Rename the symbol under the cursor",
+        &[Mode::Normal],
+        "<Leader>r",
+        "<cmd>CrabbyRename<cr>",
+    )?;
+
+    Ok(())
+}
+
+/// Request the rename of the symbol under the cursor to `new_name`, prompting for it if `None`,
+/// and preview the resulting edits.
+pub fn rename(new_name: Option<String>) -> nvim_oxi::Result<()> {
+    let lsp = vim()?.get::<Table>("lsp")?;
+
+    let Some(client) = buffer_clients(0, Some("textDocument/rename"))?
+        .into_iter()
+        .next()
+    else {
+        return Err(mlua::Error::RuntimeError(
+            "No LSP client support `textDocument/rename`".into(),
+        )
+        .into());
+    };
+
+    let new_name = match new_name {
+        Some(name) => name,
+        None => vim_fn::<String>(
+            "input",
+            table! {
+                prompt = "New name: ",
+                default = vim_fn::<String>("expand", "<cword>")?
+            },
+        )?,
+    };
+    if new_name.is_empty() {
+        return Ok(());
+    }
+
+    let offset_encoding = client.get::<String>("offset_encoding")?;
+    let params = lsp
+        .get::<Table>("util")?
+        .call_function::<Table>("make_position_params", (0, offset_encoding.as_str()))?;
+    params.set("newName", new_name)?;
+
+    client.call_method::<mlua::Value>(
+        "request",
+        (
+            "textDocument/rename",
+            params,
+            nvim_oxi::mlua::lua().create_function(
+                move |_, (err, result): (Option<Table>, Option<Table>)| {
+                    if let Some(err) = err {
+                        let message = err.get::<Option<String>>("message")?.unwrap_or_default();
+                        nvim_oxi::api::err_writeln(&format!("Rename failed: {message}"));
+                        return Ok(());
+                    }
+
+                    let Some(result) = result else {
+                        nvim_oxi::api::err_writeln("Nothing to rename");
+                        return Ok(());
+                    };
+
+                    preview(&offset_encoding, &result).map_err(mlua::Error::external)
+                },
+            )?,
+            0,
+        ),
+    )?;
+
+    Ok(())
+}
+
+/// Show the edits of `workspace_edit` in a float and apply the accepted edits on confirm.
+fn preview(offset_encoding: &str, workspace_edit: &Table) -> nvim_oxi::Result<()> {
+    let files = file_edits(offset_encoding, workspace_edit)?;
+    if files.is_empty() {
+        nvim_oxi::api::err_writeln("Nothing to rename");
+        return Ok(());
+    }
+
+    let preview = Rc::new(RefCell::new(Preview {
+        offset_encoding: offset_encoding.to_string(),
+        files,
+        lines: vec![],
+    }));
+
+    let float = Float::open("Rename", &render(&mut preview.borrow_mut()))?;
+
+    let toggle_preview = preview.clone();
+    float.set_key(
+        "<Tab>",
+        "Accept or reject the edit under the cursor",
+        move || {
+            let mut preview = toggle_preview.borrow_mut();
+            let Some(Some((file, hunk))) = preview.lines.get(float.cursor_line()? - 1).copied()
+            else {
+                return Ok(());
+            };
+
+            let file = &mut preview.files[file];
+            if let Some(hunk) = hunk {
+                file.hunks[hunk].accepted = !file.hunks[hunk].accepted;
+            } else {
+                let accepted = !file.hunks.iter().all(|hunk| hunk.accepted);
+                for hunk in &mut file.hunks {
+                    hunk.accepted = accepted;
+                }
+            }
+
+            float.set_lines(&render(&mut preview))
+        },
+    )?;
+
+    float.set_key("<CR>", "Apply the accepted edits", move || {
+        float.close()?;
+        apply(&preview.borrow())
+    })?;
+
+    Ok(())
+}
+
+/// Collect the text edits of `workspace_edit` grouped by file. Resource operations like creating or
+/// renaming files are not supported and are skipped.
+fn file_edits(offset_encoding: &str, workspace_edit: &Table) -> nvim_oxi::Result<Vec<FileEdit>> {
+    let mut edits = vec![];
+
+    if let Some(changes) = workspace_edit.get::<Option<Table>>("documentChanges")? {
+        for change in changes.sequence_values::<Table>() {
+            let change = change?;
+            match change.get::<Option<Table>>("textDocument")? {
+                Some(document) => {
+                    edits.push((
+                        document.get::<String>("uri")?,
+                        change.get::<Table>("edits")?,
+                    ));
+                }
+                None => vim_notify(
+                    &format!(
+                        "Skipping unsupported `{}` operation",
+                        change.get::<String>("kind")?
+                    ),
+                    LogLevel::Warn,
+                )?,
+            }
+        }
+    } else if let Some(changes) = workspace_edit.get::<Option<Table>>("changes")? {
+        for pair in changes.pairs::<String, Table>() {
+            edits.push(pair?);
+        }
+    }
+
+    let mut files = edits
+        .into_iter()
+        .map(|(uri, edits)| file_edit(offset_encoding, uri, &edits))
+        .collect::<nvim_oxi::Result<Vec<_>>>()?;
+    files.retain(|file| !file.hunks.is_empty());
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(files)
+}
+
+fn file_edit(offset_encoding: &str, uri: String, edits: &Table) -> nvim_oxi::Result<FileEdit> {
+    let vim = vim()?;
+    let api = vim.get::<Table>("api")?;
+
+    let fname = vim.call_function::<String>("uri_to_fname", uri.as_str())?;
+    let buf = vim_fn::<i64>("bufnr", fname.as_str())?;
+    // the files that aren't open are read from disk instead of being loaded just for the preview
+    let lines = if buf != -1 && vim_fn::<i64>("bufloaded", buf)? == 1 {
+        api.call_function::<Vec<String>>("nvim_buf_get_lines", (buf, 0, -1, false))?
+    } else {
+        vim_fn::<Vec<String>>("readfile", fname.as_str())?
+    };
+
+    let path = vim_fn::<String>("fnamemodify", (fname, ":~:."))?;
+
+    let mut hunks = vec![];
+    for edit in edits.sequence_values::<Table>() {
+        let edit = edit?;
+        let range = edit.get::<Table>("range")?;
+        let (start, end) = (range.get::<Table>("start")?, range.get::<Table>("end")?);
+        let (start_line, end_line) = (start.get::<i64>("line")?, end.get::<i64>("line")?);
+
+        let line = |line: i64| -> nvim_oxi::Result<String> {
+            Ok(usize::try_from(line)
+                .ok()
+                .and_then(|line| lines.get(line))
+                .cloned()
+                .unwrap_or_default())
+        };
+        let byte_index = |line: &str, index: i64| -> nvim_oxi::Result<usize> {
+            Ok(vim.call_function::<usize>("str_byteindex", (line, offset_encoding, index, false))?)
+        };
+
+        let before = line(start_line)?;
+        let last = if end_line == start_line {
+            before.clone()
+        } else {
+            line(end_line)?
+        };
+        let prefix = before
+            .get(..byte_index(&before, start.get::<i64>("character")?)?)
+            .unwrap_or(&before);
+        let suffix = last
+            .get(byte_index(&last, end.get::<i64>("character")?)?..)
+            .unwrap_or_default();
+
+        let after = format!("{prefix}{}{suffix}", edit.get::<String>("newText")?);
+
+        hunks.push(Hunk {
+            line: start_line,
+            before: before.trim().to_string(),
+            after: after.trim().replace('\n', "⏎"),
+            edit,
+            accepted: true,
+        });
+    }
+    hunks.sort_by_key(|hunk| hunk.line);
+
+    Ok(FileEdit { uri, path, hunks })
+}
+
+/// Render the preview and record which file and hunk each line belong to.
+fn render(preview: &mut Preview) -> Vec<String> {
+    let mut lines = vec![
+        "<Tab>: accept/reject  <CR>: apply  q: cancel".to_string(),
+        String::new(),
+    ];
+    let mut targets = vec![None, None];
+
+    for (i, file) in preview.files.iter().enumerate() {
+        lines.push(format!("{} {}", file.checkbox(), file.path));
+        targets.push(Some((i, None)));
+
+        for (j, hunk) in file.hunks.iter().enumerate() {
+            let checkbox = if hunk.accepted { "[x]" } else { "[ ]" };
+            let number = format!("{:>4}", hunk.line + 1);
+
+            lines.push(format!("  {checkbox} {number} - {}", hunk.before));
+            lines.push(format!(
+                "      {} + {}",
+                " ".repeat(number.len()),
+                hunk.after
+            ));
+            targets.extend([Some((i, Some(j))), Some((i, Some(j)))]);
+        }
+
+        lines.push(String::new());
+        targets.push(None);
+    }
+
+    preview.lines = targets;
+    lines
+}
+
+/// Apply the accepted edits, all the edits of a buffer are applied in a single change.
+fn apply(preview: &Preview) -> nvim_oxi::Result<()> {
+    let vim = vim()?;
+    let util = vim.get::<Table>("lsp")?.get::<Table>("util")?;
+    let (mut count, mut file_count) = (0, 0);
+
+    for file in &preview.files {
+        let edits = table! {};
+        for hunk in file.hunks.iter().filter(|hunk| hunk.accepted) {
+            edits.push(hunk.edit.clone())?;
+            count += 1;
+        }
+        if edits.is_empty() {
+            continue;
+        }
+
+        let buf = vim.call_function::<i64>("uri_to_bufnr", file.uri.as_str())?;
+        util.call_function::<()>(
+            "apply_text_edits",
+            (edits, buf, preview.offset_encoding.as_str()),
+        )?;
+        vim.get::<Table>("bo")?
+            .get::<Table>(buf)?
+            .set("buflisted", true)?;
+        file_count += 1;
+    }
+
+    vim_notify(
+        &format!("Applied {count} edits in {file_count} files"),
+        LogLevel::Info,
+    )
+}
@@ -0,0 +1,178 @@
+//! `:CrabbyWorkspace` to manage the workspace folders of the LSP clients.
+//!
+//! Folders added with the command are remembered for the project root of the client across
+//! sessions and are added back whenever a client attach to a buffer of that project.
+
+use mlua::{ObjectLike, Table};
+use nvim_oxi::api::Buffer;
+use nvim_oxi::api::types::LogLevel;
+
+use super::buffer_clients;
+use crate::commands::create_command_args;
+use crate::state::{load_state, save_state};
+use crate::{vim, vim_fn, vim_notify};
+
+const STATE: &str = "workspace_folders";
+
+pub(crate) fn configure() -> nvim_oxi::Result<()> {
+    create_command_args(
+        "Add, remove or list the workspace folders of the LSP clients",
+        "CrabbyWorkspace",
+        |lead, line| complete(lead, line).unwrap_or_default(),
+        |args| {
+            let mut args = args.into_iter();
+            match (args.next().as_deref(), args.next()) {
+                (Some("add"), folder) => add_folder(&resolve_folder(folder)?),
+                (Some("remove"), folder) => remove_folder(&resolve_folder(folder)?),
+                (Some("list") | None, _) => vim_notify(
+                    &format!("Workspace folders:\n{}", list_folders()?.join("\n")),
+                    LogLevel::Info,
+                ),
+                (Some(arg), _) => Err(mlua::Error::RuntimeError(format!(
+                    "Unknown argument `{arg}`, expected `add`, `remove` or `list`"
+                ))
+                .into()),
+            }
+        },
+    )?;
+
+    Ok(())
+}
+
+/// Add back the remembered workspace folders of every client attached to `buffer`.
+pub(crate) fn on_attach(buffer: &Buffer) -> nvim_oxi::Result<()> {
+    let state = load_state(STATE)?;
+    let mut folders = vec![];
+
+    for client in buffer_clients(buffer.handle().into(), None)? {
+        let Some(root) = client.get::<Option<String>>("root_dir")? else {
+            continue;
+        };
+        let existing = client_folders(&client)?;
+
+        // adding a folder already in the workspace print a message and redraw
+        for folder in state.get::<Option<Vec<String>>>(root)?.unwrap_or_default() {
+            if !existing.contains(&folder)
+                && !folders.contains(&folder)
+                && vim_fn::<i32>("isdirectory", folder.as_str())? == 1
+            {
+                folders.push(folder);
+            }
+        }
+    }
+
+    if folders.is_empty() {
+        return Ok(());
+    }
+
+    // `add_workspace_folder` only add the folder to the clients of the current buffer.
+    vim()?.get::<Table>("api")?.call_function::<()>(
+        "nvim_buf_call",
+        (
+            buffer.handle(),
+            nvim_oxi::mlua::lua().create_function(move |_, ()| {
+                for folder in &folders {
+                    vim()?
+                        .get::<Table>("lsp")?
+                        .get::<Table>("buf")?
+                        .call_function::<()>("add_workspace_folder", folder.as_str())?;
+                }
+                Ok(())
+            })?,
+        ),
+    )?;
+
+    Ok(())
+}
+
+fn add_folder(folder: &str) -> nvim_oxi::Result<()> {
+    if vim_fn::<i32>("isdirectory", folder)? != 1 {
+        return Err(mlua::Error::RuntimeError(format!("`{folder}` is not a directory")).into());
+    }
+
+    lsp_buf()?.call_function::<()>("add_workspace_folder", folder)?;
+
+    let (state, root) = (load_state(STATE)?, project_root()?);
+    let mut folders = state
+        .get::<Option<Vec<String>>>(root.as_str())?
+        .unwrap_or_default();
+
+    if !folders.iter().any(|f| f == folder) {
+        folders.push(folder.to_string());
+        state.set(root, folders)?;
+        save_state(STATE, &state)?;
+    }
+
+    Ok(())
+}
+
+fn remove_folder(folder: &str) -> nvim_oxi::Result<()> {
+    lsp_buf()?.call_function::<()>("remove_workspace_folder", folder)?;
+
+    let (state, root) = (load_state(STATE)?, project_root()?);
+    let mut folders = state
+        .get::<Option<Vec<String>>>(root.as_str())?
+        .unwrap_or_default();
+
+    folders.retain(|f| f != folder);
+    state.set(root, folders)?;
+    save_state(STATE, &state)
+}
+
+fn list_folders() -> nvim_oxi::Result<Vec<String>> {
+    Ok(lsp_buf()?.call_function::<Vec<String>>("list_workspace_folders", ())?)
+}
+
+fn complete(lead: &str, line: &str) -> nvim_oxi::Result<Vec<String>> {
+    let args = line.split_whitespace().skip(1).collect::<Vec<_>>();
+    // the argument being completed, the lead is part of the line if it isn't empty
+    let position = args.len().saturating_sub(usize::from(!lead.is_empty()));
+
+    Ok(match (position, args.first()) {
+        (0, _) => vec!["add".into(), "remove".into(), "list".into()],
+        (1, Some(&"add")) => vim_fn::<Vec<String>>("getcompletion", (lead, "dir"))?,
+        (1, Some(&"remove")) => list_folders()?,
+        _ => vec![],
+    })
+}
+
+/// Resolve `folder` to an absolute path without trailing separator, default to the directory of
+/// the current file.
+fn resolve_folder(folder: Option<String>) -> nvim_oxi::Result<String> {
+    let folder = match folder {
+        Some(folder) => vim_fn::<String>("fnamemodify", (folder, ":p"))?,
+        None => vim_fn::<String>("expand", "%:p:h")?,
+    };
+
+    Ok(folder.trim_end_matches(['/', '\\']).to_string())
+}
+
+/// The project root of the current buffer, which is the root directory of the first attached
+/// client or the working directory.
+fn project_root() -> nvim_oxi::Result<String> {
+    for client in buffer_clients(0, None)? {
+        if let Some(root) = client.get::<Option<String>>("root_dir")? {
+            return Ok(root);
+        }
+    }
+
+    Ok(vim_fn::<String>("getcwd", ())?)
+}
+
+/// The workspace folders of `client` without trailing separator, like the saved folders.
+fn client_folders(client: &Table) -> nvim_oxi::Result<Vec<String>> {
+    Ok(client
+        .get::<Option<Vec<Table>>>("workspace_folders")?
+        .unwrap_or_default()
+        .iter()
+        .map(|folder| {
+            folder
+                .get::<String>("name")
+                .map(|name| name.trim_end_matches(['/', '\\']).to_string())
+        })
+        .collect::<mlua::Result<_>>()?)
+}
+
+fn lsp_buf() -> nvim_oxi::Result<Table> {
+    Ok(vim()?.get::<Table>("lsp")?.get::<Table>("buf")?)
+}
@@ -0,0 +1,55 @@
+//! Persist small bits of config state across sessions.
+//!
+//! Each state is a lua table stored as a json file in `stdpath("state")/crabbyvim`, keyed by a
+//! short name.
+//!
+//! # Examples
+//! ```rust
+//! let state = load_state("inlay_hints")?;
+//! state.set("rust", true)?;
+//! save_state("inlay_hints", &state)?;
+//! ```
+
+use std::path::PathBuf;
+
+use mlua::{ObjectLike, Table};
+
+use crate::{table, vim, vim_fn};
+
+fn state_path(name: &str) -> nvim_oxi::Result<PathBuf> {
+    Ok(std::path::Path::new(&vim_fn::<String>("stdpath", "state")?)
+        .join("crabbyvim")
+        .join(format!("{name}.json")))
+}
+
+/// Load the state with the given `name`, an empty table is return if the state was never saved or
+/// can't be decoded.
+pub fn load_state(name: &str) -> nvim_oxi::Result<Table> {
+    let Ok(content) = std::fs::read_to_string(state_path(name)?) else {
+        return Ok(table! {});
+    };
+
+    match vim()?
+        .get::<Table>("json")?
+        .call_function::<Table>("decode", content)
+    {
+        Ok(state) => Ok(state),
+        Err(_) => Ok(table! {}),
+    }
+}
+
+/// Save `state` with the given `name` so it can be load again with [`load_state`].
+pub fn save_state(name: &str, state: &Table) -> nvim_oxi::Result<()> {
+    let path = state_path(name)?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(mlua::Error::external)?;
+    }
+
+    let json = vim()?
+        .get::<Table>("json")?
+        .call_function::<String>("encode", state.clone())?;
+
+    std::fs::write(path, json).map_err(mlua::Error::external)?;
+    Ok(())
+}
@@ -7,13 +7,31 @@
 //!     ("StatusLineNC", HighlightOpt::with_bg(STATUS_LINE_BG).fg(STATUS_LINE_FG)),
 //!     ("WinBar", HighlightOpt::with_bg(STATUS_LINE_BG).fg(STATUS_LINE_FG)),
 //!     ("WinBarNc", HighlightOpt::with_bg(STATUS_LINE_BG).fg(STATUS_LINE_FG)),
+//!     ("TabLine", HighlightOpt::with_bg(STATUS_LINE_BG).fg(STATUS_LINE_FG)),
+//!     ("TabLineFill", HighlightOpt::with_bg(STATUS_LINE_BG).fg(STATUS_LINE_FG)),
 //! ])?;
 //!```
 //!
 //! To create a new statusline (or `winbar` and `tabline`) start with `Line::new()` to make struct
-//! to build up the line components. After the line is finish make a lua function that run
-//! `Line::render()` to render the line and lastly attach this lua function to the `statusline`
-//! neovim option. You might want to also create a few autocmds to redraw the statusline.
+//! to build up the line components. After the line is finish call `Line::register()` to make a lua
+//! function that render the line and lastly attach this lua function to the `statusline` neovim
+//! option. You might want to also create a few autocmds to redraw the statusline.
+//!
+//! A line can also be built from a layout string of tile names with [`Line::from_layout`] using
+//! the tiles of a [`TileRegistry`]. The layouts of the statusline, winbar and tabline can be set
+//! with the `g:crabbyvim_statusline`, `g:crabbyvim_winbar` and `g:crabbyvim_tabline` variables,
+//! and their style and separators with `g:crabbyvim_status_style` and
+//! `g:crabbyvim_status_separators`, see [`TileStyle::from_name`] and [`Separators::from_name`].
+//!
+//! The lines and the common tiles reach Neovim through the [`Nvim`] trait returned by [`nvim`],
+//! which can be replaced with [`set_nvim`] to render lines without Neovim like in the tests.
+//!
+//! Lines and tiles can be hidden depending on the window they are rendered for, like its file
+//! type, width or if it is a floating window, with [`Line::hide_if`], [`Line::hide_tile_if`] and
+//! [`Tile::visible`].
+//!
+//! Tiles can also react to mouse clicks by implementing [`Tile::on_click`], the click is dispatched
+//! back to the tile by the lua function registered with [`Line::register`].
 //!
 //! A bunch of existing common components are provided but more can be created by makign a new type
 //! that implemented the [`Tile`] trait. A few tile require the use of a `new()` method like
@@ -23,12 +41,23 @@
 // If you want a lua version of this statusline check out the old version:
 // https://github.com/Mouthless-Stoat/Nvim-config/blob/913333d01835ac974d7079bdc5cf9fbb03d869a5/lua/config/theme/plugins/status.lua
 
-use nvim_oxi::api::types::StatuslineInfos;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use mlua::Table;
+use nvim_oxi::api::types::{LogLevel, StatuslineInfos};
 
 use crate::{
-    autocmds::{create_autocmd_cmd, create_autocmd_oneshot},
-    options::{get_option, set_option},
-    theme::{Color, HighlightOpt, configure_highlights, set_hl},
+    autocmds::{create_autocmd, create_autocmd_cmd, create_autocmd_oneshot},
+    commands::create_command_args,
+    float::Float,
+    icons,
+    options::set_option,
+    table,
+    theme::{Color, HighlightOpt, configure_highlights},
+    vim_fn, vim_notify,
 };
 
 mod global_tiles;
@@ -37,6 +66,21 @@ pub use global_tiles::*;
 mod local_tiles;
 pub use local_tiles::*;
 
+mod tab_tiles;
+pub use tab_tiles::*;
+
+mod breadcrumbs;
+pub use breadcrumbs::*;
+
+mod layout;
+pub use layout::*;
+
+mod nvim;
+pub use nvim::*;
+
+#[cfg(test)]
+mod tests;
+
 /// The color of the statusline background
 pub const STATUS_LINE_BG: Color = crate::theme::Color::Bg1;
 /// The color of the statusline foreground
@@ -49,69 +93,272 @@ pub(crate) fn configure() -> nvim_oxi::Result<()> {
         ("StatusLineNC", HighlightOpt::with_bg(STATUS_LINE_BG).fg(STATUS_LINE_FG)),
         ("WinBar", HighlightOpt::with_bg(STATUS_LINE_BG).fg(STATUS_LINE_FG)),
         ("WinBarNc", HighlightOpt::with_bg(STATUS_LINE_BG).fg(STATUS_LINE_FG)),
+        ("TabLine", HighlightOpt::with_bg(STATUS_LINE_BG).fg(STATUS_LINE_FG)),
+        ("TabLineFill", HighlightOpt::with_bg(STATUS_LINE_BG).fg(STATUS_LINE_FG)),
     ])?;
 
     set_option("laststatus", 3)?;
 
-    let mut statusline = Line::new();
-
-    statusline.add_left(Mode::new());
-    statusline.add_left(Cwd::new());
-    statusline.add_left(GitBranch);
-    statusline.add_left(Diagnostic::new(true));
-    if nvim_oxi::api::get_var::<bool>("neovide").is_ok() {
-        statusline.add_right(Zoom);
-    }
-    statusline.add_right(Loc);
+    let registry = TileRegistry::default();
 
-    let mut winbar = Line::new();
+    let zoom = if nvim_oxi::api::get_var::<bool>("neovide").is_ok() {
+        " zoom"
+    } else {
+        ""
+    };
+    let mut statusline = configured_line(
+        "crabbyvim_statusline",
+        &format!(
+            "mode recording cwd git_branch git_sync diagnostics \
+             > lsp_progress{zoom} search_count selection loc"
+        ),
+        &registry,
+    )?;
+    statusline.full_width();
 
-    winbar.add_left(GitDiff);
-    winbar.add_left(Diagnostic::new(false));
-    winbar.add_center(FileName::new());
-    winbar.add_right_center(Tools::new());
-    winbar.add_right_center(FileStatus::new());
-    winbar.add_right(AltFileName::new());
+    let mut winbar = configured_line(
+        "crabbyvim_winbar",
+        "git_diff buffer_diagnostics word_count | filename breadcrumbs \
+         | inlay_hints tools file_format file_status > alt_filename",
+        &registry,
+    )?;
 
+    winbar.dim_inactive();
+    winbar.hide_if(|win| win.float);
+    winbar.exclude_ft("oil");
     winbar.exclude_ft("snacks_dashboard");
     winbar.exclude_ft("snacks_terminal");
     winbar.exclude_ft("undotree");
     winbar.exclude_ft("diff");
 
-    #[rustfmt::skip]
-    nvim_oxi::mlua::lua().globals().set(
-        "statusline",
-        nvim_oxi::mlua::lua().create_function_mut(move |_, ()| {
-            Ok(statusline.render().expect("Can't render statusline"))
-        })?,
-    )?;
+    let mut tabline = configured_line("crabbyvim_tabline", "buffers > tabs", &registry)?;
+    tabline.full_width();
 
-    #[rustfmt::skip]
-    nvim_oxi::mlua::lua().globals().set(
-        "winbar",
-        nvim_oxi::mlua::lua() .create_function_mut(move |_, ()| {
-            Ok(winbar.render().expect("Can't render winbar"))
-        })?,
+    // like `plain` and `ascii` for terminals without Nerd Fonts
+    let style = configured_look("crabbyvim_status_style", TileStyle::from_name)?;
+    let separators = configured_look("crabbyvim_status_separators", Separators::from_name)?;
+    for line in [&mut statusline, &mut winbar, &mut tabline] {
+        if let Some(style) = style {
+            line.set_style(style);
+        }
+        if let Some(separators) = separators {
+            line.set_separators(separators);
+        }
+    }
+
+    let lines = [
+        ("statusline", statusline.register("statusline")?),
+        ("winbar", winbar.register("winbar")?),
+        ("tabline", tabline.register("tabline")?),
+    ];
+
+    create_command_args(
+        "Measure the render time of every status line tile",
+        "CrabbyStatusBench",
+        |_, _| vec![],
+        move |args| {
+            let iterations = match args.first() {
+                Some(arg) => arg.parse::<u32>().map_err(mlua::Error::external)?,
+                None => 100,
+            };
+
+            let mut report = vec![format!("Average over {iterations} renders"), String::new()];
+            for (name, line) in &lines {
+                report.push((*name).to_string());
+                report.extend(line.borrow_mut().bench(iterations)?);
+                report.push(String::new());
+            }
+
+            Float::open("Status Bench", &report)?;
+            Ok(())
+        },
     )?;
 
     create_autocmd_oneshot("User", &["VeryLazy"], |_| {
         set_option("statusline", "%!v:lua.statusline()")?;
         set_option("winbar", "%{%v:lua.winbar()%}")?;
+        set_option("tabline", "%!v:lua.tabline()")?;
+        update_showtabline(None)?;
         create_autocmd_cmd(&["User"], &["GitSignsUpdate"], "redrawstatus!")?;
         create_autocmd_cmd(&["DiagnosticChanged"], &["*"], "redrawstatus!")?;
+        create_autocmd_cmd(
+            &["DiagnosticChanged", "BufModifiedSet"],
+            &["*"],
+            "redrawtabline",
+        )?;
+        create_autocmd(
+            &["BufAdd", "BufDelete", "TabNew", "TabClosed"],
+            &["*"],
+            |args| update_showtabline((args.event == "BufDelete").then_some(args.buffer.handle())),
+        )?;
+        create_autocmd(&["OptionSet"], &["buflisted"], |_| update_showtabline(None))?;
         Ok(())
     })?;
 
     Ok(())
 }
 
+/// Create a line from the layout string in `g:{var}`, see [`Line::from_layout`]. The `default`
+/// layout is used when the variable isn't set or its layout is invalid.
+fn configured_line(var: &str, default: &str, registry: &TileRegistry) -> nvim_oxi::Result<Line> {
+    if let Ok(layout) = nvim_oxi::api::get_var::<String>(var) {
+        match Line::from_layout(&layout, registry) {
+            Ok(line) => return Ok(line),
+            Err(err) => vim_notify(&format!("{err}, using the default layout"), LogLevel::Warn)?,
+        }
+    }
+
+    Line::from_layout(default, registry)
+}
+
+/// Read the name in `g:{var}` with `from_name`, `None` when the variable isn't set or its name is
+/// unknown.
+fn configured_look<T>(var: &str, from_name: fn(&str) -> Option<T>) -> nvim_oxi::Result<Option<T>> {
+    let Ok(name) = nvim_oxi::api::get_var::<String>(var) else {
+        return Ok(None);
+    };
+
+    let look = from_name(&name);
+    if look.is_none() {
+        vim_notify(
+            &format!("Unknown `g:{var}` value `{name}`, using the default"),
+            LogLevel::Warn,
+        )?;
+    }
+    Ok(look)
+}
+
+/// Only show the tabline when there is more than one listed buffer or tab page. `deleted` is a
+/// buffer about to be deleted that shouldn't be counted.
+fn update_showtabline(deleted: Option<i32>) -> nvim_oxi::Result<()> {
+    let buffers = vim_fn::<Vec<Table>>("getbufinfo", table! { buflisted = 1 })?
+        .iter()
+        .map(|info| info.get::<i32>("bufnr"))
+        .filter(|buf| buf.as_ref().ok() != deleted.as_ref())
+        .count();
+    let tabs = vim_fn::<usize>("tabpagenr", "$")?;
+
+    set_option("showtabline", if buffers > 1 || tabs > 1 { 2 } else { 0 })
+}
+
 /// Style for a given statusline tile.
+///
+/// The style is chosen per tile with [`Tile::style`] or for every tile of a line with
+/// [`Line::set_style`].
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum TileStyle {
     /// Content of the tile wrap in a little bubble all with the same background color
     Bubble,
     /// Content of the tile next to an icon with background color while the main contain only have
     /// it foreground be colored.
     Icon,
+    /// Like [`TileStyle::Bubble`] but with the arrow separators of powerline.
+    Powerline,
+    /// Like [`TileStyle::Bubble`] but with slanted separators.
+    Slant,
+    /// Like [`TileStyle::Bubble`] but with half block separators that doesn't need a Nerd Font.
+    Block,
+    /// Only the foreground of the content is colored, between ASCII brackets and without icon.
+    /// The tiles also swap their glyphs for ASCII, see [`glyph`], so it can be used on terminals
+    /// without Nerd Fonts.
+    Plain,
+}
+
+impl TileStyle {
+    /// The default separators drawn around a tile of this style.
+    #[must_use]
+    pub fn separators(self) -> Separators {
+        match self {
+            TileStyle::Bubble | TileStyle::Icon => Separators::ROUNDED,
+            TileStyle::Powerline => Separators::POWERLINE,
+            TileStyle::Slant => Separators::SLANT,
+            TileStyle::Block => Separators::BLOCK,
+            TileStyle::Plain => Separators::ASCII,
+        }
+    }
+
+    /// The style named `name` in `g:crabbyvim_status_style`, the name of the variant in snake
+    /// case.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "bubble" => TileStyle::Bubble,
+            "icon" => TileStyle::Icon,
+            "powerline" => TileStyle::Powerline,
+            "slant" => TileStyle::Slant,
+            "block" => TileStyle::Block,
+            "plain" => TileStyle::Plain,
+            _ => return None,
+        })
+    }
+
+    /// The style `tile` is rendered with in a line using `line_style`.
+    ///
+    /// A line can't force [`TileStyle::Icon`] on a tile since it might not have an icon.
+    fn resolve(line_style: Option<TileStyle>, tile: &dyn Tile) -> TileStyle {
+        match line_style {
+            Some(TileStyle::Icon) | None => tile.style(),
+            Some(style) => style,
+        }
+    }
+}
+
+/// The glyphs drawn on the left and right of a tile, see [`Line::set_separators`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Separators {
+    /// Drawn before the tile.
+    pub left: &'static str,
+    /// Drawn after the tile.
+    pub right: &'static str,
+}
+
+impl Separators {
+    /// Half circles, the default of [`TileStyle::Bubble`] and [`TileStyle::Icon`].
+    pub const ROUNDED: Self = Self::new(icons::ROUNDED_LEFT, icons::ROUNDED_RIGHT);
+    /// Powerline arrows, the default of [`TileStyle::Powerline`].
+    pub const POWERLINE: Self = Self::new(icons::POWERLINE_LEFT, icons::POWERLINE_RIGHT);
+    /// Slanted triangles, the default of [`TileStyle::Slant`].
+    pub const SLANT: Self = Self::new(icons::SLANT_LEFT, icons::SLANT_RIGHT);
+    /// Half blocks from unicode that doesn't need a Nerd Font, the default of
+    /// [`TileStyle::Block`].
+    pub const BLOCK: Self = Self::new(icons::BLOCK_LEFT, icons::BLOCK_RIGHT);
+    /// Plain ASCII brackets for terminals without Nerd Fonts, the default of [`TileStyle::Plain`].
+    pub const ASCII: Self = Self::new("[", "]");
+    /// No separator, the tiles are only separated by a space.
+    pub const NONE: Self = Self::new("", "");
+
+    /// Separators drawing `left` before the tile and `right` after it.
+    #[must_use]
+    pub const fn new(left: &'static str, right: &'static str) -> Self {
+        Self { left, right }
+    }
+
+    /// The separators named `name` in `g:crabbyvim_status_separators`, the name of the constant
+    /// in lower case.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "rounded" => Self::ROUNDED,
+            "powerline" => Self::POWERLINE,
+            "slant" => Self::SLANT,
+            "block" => Self::BLOCK,
+            "ascii" => Self::ASCII,
+            "none" => Self::NONE,
+            _ => return None,
+        })
+    }
+}
+
+thread_local! {
+    /// Whether the tile being rendered use [`TileStyle::Plain`], see [`glyph`].
+    static PLAIN: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Return `ascii` in place of the Nerd Font `icon` when the tile being rendered use
+/// [`TileStyle::Plain`], for the glyphs the tiles put in their content.
+#[must_use]
+pub fn glyph<'a>(icon: &'a str, ascii: &'a str) -> &'a str {
+    if PLAIN.get() { ascii } else { icon }
 }
 
 /// Trait for a tile to implement. Each tile need to implement:
@@ -125,7 +372,7 @@ pub enum TileStyle {
 /// Other important method like [`Tile::setup`] and [`Tile::update`] use to create or update the
 /// value.
 pub trait Tile {
-    /// The style of this tile.
+    /// The style of this tile, overridden by [`Line::set_style`].
     ///
     /// If this option return [`TileStyle::Icon`], [`Tile::icon`] must be
     /// implemented as well to have an icon to render.
@@ -134,6 +381,9 @@ pub trait Tile {
     }
 
     /// Return the icons to be used with the [`TileStyle::Icon`] style.
+    ///
+    /// When the line render the tile with another style the icon is put before the content, except
+    /// for [`TileStyle::Plain`].
     fn icon(&self) -> nvim_oxi::Result<String> {
         Ok(String::new())
     }
@@ -179,10 +429,206 @@ pub trait Tile {
     fn update_highlight(&self, old_opt: HighlightOpt) -> nvim_oxi::Result<HighlightOpt> {
         Ok(old_opt)
     }
+
+    /// Return `true` if the tile should react to mouse clicks with [`Tile::on_click`].
+    fn clickable(&self) -> bool {
+        false
+    }
+
+    /// Priority of the tile when the line doesn't fit in the window.
+    ///
+    /// The tiles with the lowest priority are switched to their compact form, see
+    /// [`Tile::compact_content`], then hidden first.
+    fn priority(&self) -> u8 {
+        50
+    }
+
+    /// A shorter version of [`Tile::content`] to use when the line doesn't fit in the window,
+    /// `None` if the tile doesn't have a compact form.
+    fn compact_content(&self) -> nvim_oxi::Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Events that change the content of the tile, `User` events are written with their pattern
+    /// like `User GitSignsUpdate`.
+    ///
+    /// The rendered tile is cached until one of the events fire, `None` disable the cache and the
+    /// tile is rendered on every redraw while an empty list cache the tile forever.
+    fn events(&self) -> Option<&'static [&'static str]> {
+        None
+    }
+
+    /// Name of the tile used by [`Line::hide_tile_if`], in the error messages and by
+    /// `:CrabbyStatusBench` when the tile isn't added by [`Line::from_layout`].
+    fn name(&self) -> &'static str {
+        let name = std::any::type_name::<Self>();
+        name.rsplit("::").next().unwrap_or(name)
+    }
+
+    /// Return `true` for a tile taking the width left by the other tiles of the line, like
+    /// [`BufferList`]. That width is given to [`Tile::set_width`] before the tile is rendered.
+    fn fills_line(&self) -> bool {
+        false
+    }
+
+    /// Give the width left by the other tiles of the line to a tile that [`Tile::fills_line`],
+    /// return `true` if the width changed so the cached render of the tile is dropped.
+    fn set_width(&mut self, _width: usize) -> bool {
+        false
+    }
+
+    /// Return `false` to hide the tile in the window described by `win`, on top of the rules added
+    /// with [`Line::hide_tile_if`].
+    fn visible(&self, _win: &WinContext) -> bool {
+        true
+    }
+
+    /// Called when the tile is clicked, only if [`Tile::clickable`] return `true`.
+    ///
+    /// `clicks` is the number of clicks, `button` is `l`, `r` or `m` for the mouse button and
+    /// `modifiers` contain `s`, `c`, `a` and `m` for each of the modifier keys held.
+    fn on_click(&mut self, _clicks: i64, _button: &str, _modifiers: &str) -> nvim_oxi::Result<()> {
+        Ok(())
+    }
+}
+
+/// Type alias for a collection of [`Tile`] as well as their [`HighlightOpt`], render caches,
+/// failures and names.
+pub type Tiles = Vec<TileEntry>;
+
+/// A [`Tile`] of a line along with its [`HighlightOpt`], render cache, failures and name. The name
+/// is the one used in the layout for the tiles added by [`Line::from_layout`] and [`Tile::name`]
+/// otherwise.
+pub type TileEntry = (
+    Box<dyn Tile>,
+    HighlightOpt,
+    TileCache,
+    TileHealth,
+    &'static str,
+);
+
+/// The window a line is rendered for, given to the rules deciding if a line or a tile is shown.
+pub struct WinContext {
+    /// The `buftype` of the buffer, empty for normal file buffers.
+    pub buftype: String,
+    /// The `filetype` of the buffer.
+    pub filetype: String,
+    /// The width available to the line, the whole editor for a full width line.
+    pub width: usize,
+    /// Whether the window is a floating window.
+    pub float: bool,
+    /// Whether the window is in diff mode.
+    pub diff: bool,
 }
 
-/// Type alias for a collection of [`Tile`] as well as their [`HighlightOpt`] caches
-pub type Tiles = Vec<(Box<dyn Tile>, HighlightOpt)>;
+/// A rule hiding a line or a tile in the windows where it return `true`, see [`Line::hide_if`]
+/// and [`Line::hide_tile_if`].
+pub type HideRule = Box<dyn Fn(&WinContext) -> bool>;
+
+/// Failures in a row after which a tile is disabled.
+const MAX_TILE_FAILURES: u32 = 5;
+
+/// Time after which a disabled tile is tried again, like when the plugin it needs is loaded late.
+const TILE_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// The render failures of a tile. A failing tile is rendered as an error marker, the first error
+/// is logged and the tile is disabled after [`MAX_TILE_FAILURES`] failures in a row or when it
+/// can't be setup. A disabled tile is tried again after [`TILE_RETRY_DELAY`].
+#[derive(Default)]
+pub struct TileHealth {
+    failures: u32,
+    logged: bool,
+    disabled_at: Option<Instant>,
+    /// Whether [`Tile::setup`] failed and must be called again before the tile is rendered.
+    needs_setup: bool,
+}
+
+impl TileHealth {
+    /// Whether the tile is disabled, the tile is enabled again once [`TILE_RETRY_DELAY`] passed.
+    fn disabled(&mut self) -> bool {
+        match self.disabled_at {
+            Some(at) if at.elapsed() < TILE_RETRY_DELAY => true,
+            Some(_) => {
+                self.failures = 0;
+                self.disabled_at = None;
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Record a failure of the tile `name`, return `false` once the tile is disabled.
+    fn fail(&mut self, name: &str, err: &nvim_oxi::Error) -> nvim_oxi::Result<bool> {
+        self.failures += 1;
+        if self.failures >= MAX_TILE_FAILURES {
+            self.disabled_at = Some(Instant::now());
+        }
+        if !self.logged {
+            self.logged = true;
+            nvim().notify(
+                &format!(
+                    "The {name} tile failed, it is disabled for {}s after {MAX_TILE_FAILURES} \
+                     failures in a row: {err}",
+                    TILE_RETRY_DELAY.as_secs()
+                ),
+                LogLevel::Error,
+            )?;
+        }
+        Ok(self.disabled_at.is_none())
+    }
+
+    /// Disable the tile `name` right away because it can't be setup, the setup is tried again
+    /// with the tile.
+    fn disable(&mut self, name: &str, err: &nvim_oxi::Error) -> nvim_oxi::Result<()> {
+        self.disabled_at = Some(Instant::now());
+        self.needs_setup = true;
+        if self.logged {
+            return Ok(());
+        }
+        self.logged = true;
+        nvim().notify(
+            &format!(
+                "The {name} tile can't be setup, it is tried again in {}s: {err}",
+                TILE_RETRY_DELAY.as_secs()
+            ),
+            LogLevel::Error,
+        )
+    }
+}
+
+/// The window and whether the line is dimmed, see [`Line::dim_inactive`].
+type CacheKey = (i64, bool);
+
+/// Cache of the rendered tile for each window, cleared when one of the [`Tile::events`] fire and
+/// pruned when a window is closed.
+#[derive(Default, Clone)]
+pub struct TileCache(Rc<RefCell<HashMap<CacheKey, Option<RenderedTile>>>>);
+
+impl TileCache {
+    fn get(&self, key: CacheKey) -> Option<Option<RenderedTile>> {
+        self.0.borrow().get(&key).cloned()
+    }
+
+    fn insert(&self, key: CacheKey, tile: Option<RenderedTile>) {
+        self.0.borrow_mut().insert(key, tile);
+    }
+
+    fn clear(&self) {
+        self.0.borrow_mut().clear();
+    }
+
+    /// Drop the renders of the closed window `win`.
+    fn remove_win(&self, win: i64) {
+        self.0.borrow_mut().retain(|(cached, _), _| *cached != win);
+    }
+
+    /// Clear the cache whenever one of `events` fire. `User` events are written with their pattern
+    /// like `User GitSignsUpdate`.
+    fn invalidate_on(&self, events: &[&str]) -> nvim_oxi::Result<()> {
+        let cache = self.clone();
+        nvim().on_events(events, Rc::new(move || cache.clear()))
+    }
+}
 
 /// A line of [`Tile`] separated into section to be align.
 ///
@@ -202,7 +648,42 @@ pub struct Line {
     center: Tiles,
     right_center: Tiles,
     right: Tiles,
-    exclude_ft: Vec<&'static str>,
+    hide_rules: Vec<HideRule>,
+    /// Rules hiding the tiles with the given name, see [`TileEntry`].
+    tile_hide_rules: Vec<(&'static str, HideRule)>,
+    /// Name of the lua function that dispatch the clicks to the tiles.
+    click_handler: Option<String>,
+    full_width: bool,
+    /// Line to render instead of this one for the windows that aren't the current window.
+    inactive: Option<Box<Line>>,
+    dim_inactive: bool,
+    /// Style used for every tile instead of [`Tile::style`].
+    style: Option<TileStyle>,
+    /// Separators used for every tile instead of [`TileStyle::separators`].
+    separators: Option<Separators>,
+}
+
+/// A tile rendered by [`Line::render`] along with its width to fit the line in the window.
+#[derive(Clone)]
+struct RenderedTile {
+    priority: u8,
+    full: (String, usize),
+    compact: Option<(String, usize)>,
+    compacted: bool,
+    hidden: bool,
+}
+
+impl RenderedTile {
+    /// The rendered tile and its width in its current form, `None` if the tile is hidden.
+    fn current(&self) -> Option<&(String, usize)> {
+        if self.hidden {
+            None
+        } else if self.compacted {
+            self.compact.as_ref()
+        } else {
+            Some(&self.full)
+        }
+    }
 }
 
 impl Line {
@@ -215,30 +696,29 @@ impl Line {
         }
     }
 
-    fn set_hl(tile: &dyn Tile, hl_opt: HighlightOpt) -> nvim_oxi::Result<()> {
+    fn set_hl(tile: &dyn Tile, hl_opt: HighlightOpt, style: TileStyle) -> nvim_oxi::Result<()> {
+        let nvim = nvim();
         let norm_hl = tile.highlight_name()?;
-        match tile.style() {
-            TileStyle::Bubble => {
-                set_hl(
-                    tile.highlight_name()?,
-                    hl_opt.clone().fg_if_none(STATUS_LINE_BG),
-                )?;
-                set_hl(
-                    tile.highlight_rev_name(norm_hl.clone())?,
+        match style {
+            TileStyle::Bubble
+            | TileStyle::Powerline
+            | TileStyle::Slant
+            | TileStyle::Block
+            | TileStyle::Plain => {
+                nvim.set_hl(&norm_hl, hl_opt.clone().fg_if_none(STATUS_LINE_BG))?;
+                nvim.set_hl(
+                    &tile.highlight_rev_name(norm_hl.clone())?,
                     hl_opt.reverse_fg_bg().bg(STATUS_LINE_BG),
                 )?;
             }
             TileStyle::Icon => {
-                set_hl(
-                    tile.highlight_name()?,
-                    hl_opt.clone().fg_if_none(STATUS_LINE_BG),
-                )?;
-                set_hl(
-                    tile.highlight_rev_name(norm_hl.clone())?,
+                nvim.set_hl(&norm_hl, hl_opt.clone().fg_if_none(STATUS_LINE_BG))?;
+                nvim.set_hl(
+                    &tile.highlight_rev_name(norm_hl.clone())?,
                     hl_opt.clone().reverse_fg_bg().bg(STATUS_LINE_FG),
                 )?;
-                set_hl(
-                    tile.highlight_sep_name(norm_hl.clone())?,
+                nvim.set_hl(
+                    &tile.highlight_sep_name(norm_hl.clone())?,
                     hl_opt.reverse_fg_bg().bg(STATUS_LINE_BG),
                 )?;
             }
@@ -246,149 +726,636 @@ impl Line {
         Ok(())
     }
 
+    /// Set the highlights of a tile and call [`Tile::setup`], the tile is disabled if it fails.
+    fn setup_tile(
+        (tile, _, cache, health, name): &mut TileEntry,
+        style: Option<TileStyle>,
+    ) -> nvim_oxi::Result<()> {
+        let result = Line::set_hl(
+            &**tile,
+            tile.highlight_opt(),
+            TileStyle::resolve(style, &**tile),
+        )
+        .and_then(|()| tile.setup());
+        if let Err(err) = result {
+            return health.disable(name, &err);
+        }
+        health.needs_setup = false;
+
+        if let Some(events) = tile.events() {
+            cache.invalidate_on(events)?;
+        }
+        Ok(())
+    }
+
     /// Don't call this method manually
     fn setup(&mut self) -> nvim_oxi::Result<()> {
-        fn setup_section(section: &Tiles) -> nvim_oxi::Result<()> {
-            if !section.is_empty() {
-                for (tile, _) in section {
-                    Line::set_hl(&**tile, tile.highlight_opt())?;
-                    tile.setup()?;
-                }
+        fn setup_section(section: &mut Tiles, style: Option<TileStyle>) -> nvim_oxi::Result<()> {
+            for tile in section {
+                Line::setup_tile(tile, style)?;
             }
             Ok(())
         }
 
-        setup_section(&self.left)?;
-        setup_section(&self.left_center)?;
-        setup_section(&self.center)?;
-        setup_section(&self.right_center)?;
-        setup_section(&self.right)?;
+        let style = self.style;
+        for section in self.sections_mut() {
+            setup_section(section, style)?;
+        }
+
+        // full width lines are cached for every window at once
+        if !self.full_width {
+            let caches = self
+                .sections_mut()
+                .into_iter()
+                .flatten()
+                .map(|(_, _, cache, ..)| cache.clone())
+                .collect::<Vec<_>>();
+            nvim().on_win_closed(Rc::new(move |win| {
+                caches.iter().for_each(|cache| cache.remove_win(win));
+            }))?;
+        }
+
+        nvim().set_hl(
+            "StatusTileError",
+            HighlightOpt::with_fg(Color::Red).bg(STATUS_LINE_BG),
+        )?;
+
+        if self.dim_inactive {
+            for (name, opt) in [
+                (
+                    "StatusInactive",
+                    HighlightOpt::with_bg(Color::Gray).fg(STATUS_LINE_BG),
+                ),
+                (
+                    "StatusInactiveRev",
+                    HighlightOpt::with_fg(Color::Gray).bg(STATUS_LINE_BG),
+                ),
+                (
+                    "StatusInactiveText",
+                    HighlightOpt::with_fg(Color::Gray).bg(STATUS_LINE_FG),
+                ),
+            ] {
+                nvim().set_hl(name, opt)?;
+            }
+        }
 
         self.not_setup = false;
 
         Ok(())
     }
 
-    /// Return the rendered version of this line.
+    /// Register the lua function `name` to render this line to be use in an option as
+    /// `%!v:lua.{name}()` as well as the `{name}_click` function to dispatch clicks to the tiles.
+    ///
+    /// The registered line is returned so it can still be accessed, like for [`Line::bench`].
+    pub fn register(mut self, name: &'static str) -> nvim_oxi::Result<Rc<RefCell<Line>>> {
+        let lua = nvim_oxi::mlua::lua();
+        self.click_handler = Some(format!("{name}_click"));
+
+        let line = Rc::new(RefCell::new(self));
+        let (render_line, click_line) = (line.clone(), line.clone());
+        let reported = Cell::new(false);
+
+        lua.globals().set(
+            name,
+            lua.create_function(move |_, ()| {
+                Ok(render_line.borrow_mut().render().unwrap_or_else(|err| {
+                    // the line is redrawn all the time so the error is only shown once
+                    if !reported.replace(true) {
+                        nvim_oxi::api::err_writeln(&format!("Can't render {name}: {err}"));
+                    }
+                    String::new()
+                }))
+            })?,
+        )?;
+
+        lua.globals().set(
+            format!("{name}_click"),
+            lua.create_function(
+                move |_, (id, clicks, button, modifiers): (usize, i64, String, String)| {
+                    if let Err(err) = click_line
+                        .borrow_mut()
+                        .on_click(id, clicks, &button, &modifiers)
+                    {
+                        nvim_oxi::api::err_writeln(&format!("Can't handle {name} click: {err}"));
+                    }
+                    Ok(())
+                },
+            )?,
+        )?;
+
+        Ok(line)
+    }
+
+    /// Measure the render time of the line with and without the cache as well as the time each
+    /// tile take to update and compute its content, averaged over `iterations`.
+    pub fn bench(&mut self, iterations: u32) -> nvim_oxi::Result<Vec<String>> {
+        let iterations = iterations.max(1);
+        let mut report = vec![];
+
+        let start = Instant::now();
+        for _ in 0..iterations {
+            self.render()?;
+        }
+        report.push(format!(
+            "  cached line   {:>10.1?}",
+            start.elapsed() / iterations
+        ));
+
+        let start = Instant::now();
+        for _ in 0..iterations {
+            self.sections_mut()
+                .into_iter()
+                .flatten()
+                .for_each(|(_, _, cache, ..)| cache.clear());
+            self.render()?;
+        }
+        report.push(format!(
+            "  uncached line {:>10.1?}",
+            start.elapsed() / iterations
+        ));
+
+        for (tile, .., name) in self.sections_mut().into_iter().flatten() {
+            let start = Instant::now();
+            for _ in 0..iterations {
+                tile.update()?;
+                tile.content()?;
+                tile.compact_content()?;
+            }
+            report.push(format!(
+                "  {:<13} {:>10.1?}{}",
+                name,
+                start.elapsed() / iterations,
+                if tile.events().is_some() {
+                    " (cached)"
+                } else {
+                    ""
+                }
+            ));
+        }
+
+        Ok(report)
+    }
+
+    fn sections_mut(&mut self) -> [&mut Tiles; 5] {
+        [
+            &mut self.left,
+            &mut self.left_center,
+            &mut self.center,
+            &mut self.right_center,
+            &mut self.right,
+        ]
+    }
+
+    /// Dispatch a click to the tile with the index `id`, counting from the first tile of the left
+    /// section.
+    fn on_click(
+        &mut self,
+        mut id: usize,
+        clicks: i64,
+        button: &str,
+        modifiers: &str,
+    ) -> nvim_oxi::Result<()> {
+        for section in self.sections_mut() {
+            if let Some((tile, ..)) = section.get_mut(id) {
+                return tile.on_click(clicks, button, modifiers);
+            }
+            id -= section.len();
+        }
+
+        Ok(())
+    }
+
+    /// Return the rendered version of this line for the window it is drawn for, which is
+    /// `g:statusline_winid` during the evaluation of `statusline` and `winbar`.
+    ///
+    /// The tiles are rendered in the context of that window so they show the information of its
+    /// buffer. When the line doesn't fit in the window, the tiles with the lowest
+    /// [`Tile::priority`] are compacted then hidden until it does.
     pub fn render(&mut self) -> nvim_oxi::Result<String> {
-        fn render_section(section: &mut Tiles) -> nvim_oxi::Result<String> {
-            if section.is_empty() {
-                return Ok(String::new());
+        let nvim = nvim();
+        let current = nvim.current_win()?;
+        let win = nvim.status_window()?;
+        let active = self.full_width || win == current;
+
+        if !active && let Some(inactive) = &mut self.inactive {
+            return inactive.render();
+        }
+
+        let dim = !active && self.dim_inactive;
+        if win == current {
+            return self.render_tiles(dim);
+        }
+
+        nvim.win_call(win, &mut || self.render_tiles(dim))
+    }
+
+    /// Render the tiles in the context of the current window, `dim` render all the tiles with the
+    /// inactive highlight groups.
+    fn render_tiles(&mut self, dim: bool) -> nvim_oxi::Result<String> {
+        /// Render a single tile, `None` if the tile has no content.
+        fn render_tile(
+            (tile, hl, .., name): &mut TileEntry,
+            id: usize,
+            click_handler: Option<&str>,
+            dim: bool,
+            (line_style, separators): (Option<TileStyle>, Option<Separators>),
+        ) -> nvim_oxi::Result<Option<RenderedTile>> {
+            tile.update()?;
+
+            let content = tile.content()?;
+
+            if content.is_empty() {
+                return Ok(None);
             }
-            let mut sections: Vec<String> = vec![];
 
-            for tile in section {
-                tile.0.update()?;
+            let style = TileStyle::resolve(line_style, &**tile);
 
-                let content = tile.0.content()?;
+            // We can use clone here without much performance issue because all of the
+            // highlights group shouldn't be link to anything so we never have to clone a
+            // string.
+            // This code only make the set_hl call when it is actually necessary for better
+            // performance by caching the value.
+            let old_hl = hl.clone();
+            *hl = tile.update_highlight(hl.clone())?;
+            if old_hl != *hl {
+                Line::set_hl(&**tile, hl.clone(), style)?;
+            }
 
-                if content.is_empty() {
-                    continue;
-                }
+            let Separators { left, right } = separators.unwrap_or(style.separators());
+            // the icon of icon tiles is kept when the line use another style
+            let icon = if tile.style() == TileStyle::Icon {
+                tile.icon()?
+            } else {
+                String::new()
+            };
 
-                let norm = tile.0.highlight_name()?;
-                let rev = tile.0.highlight_rev_name(norm.clone())?;
-
-                // We can use clone here without much performance issue because all of the
-                // highlights group shouldn't be link to anything so we never have to clone a
-                // string.
-                // This code only make the set_hl call when it is actually necessary for better
-                // performance by caching the value.
-                let old_hl = tile.1.clone();
-                tile.1 = tile.0.update_highlight(tile.1.clone())?;
-                if old_hl != tile.1 {
-                    Line::set_hl(&*tile.0, tile.1.clone())?;
-                }
+            let decorate = |content: &str| -> nvim_oxi::Result<(String, usize)> {
+                let (norm, rev, sep) = if dim {
+                    let rev = if style == TileStyle::Icon {
+                        "StatusInactiveText"
+                    } else {
+                        "StatusInactiveRev"
+                    };
+                    (
+                        "StatusInactive".into(),
+                        rev.into(),
+                        "StatusInactiveRev".into(),
+                    )
+                } else {
+                    let norm = tile.highlight_name()?;
+                    let rev = tile.highlight_rev_name(norm.clone())?;
+                    let sep = tile.highlight_sep_name(norm.clone())?;
+                    (norm, rev, sep)
+                };
 
-                let tile = match tile.0.style() {
-                    TileStyle::Bubble => format!("%#{rev}#%#{norm}#{content}%#{rev}#%*",),
+                let decorated = match style {
                     TileStyle::Icon => {
-                        assert!(!tile.0.icon()?.is_empty());
-                        let sep = tile.0.highlight_sep_name(norm.clone())?;
+                        if icon.is_empty() {
+                            return Err(mlua::Error::RuntimeError(format!(
+                                "The {name} tile has no icon for the icon style"
+                            ))
+                            .into());
+                        }
+
+                        format!("%#{sep}#{left}%#{norm}#{icon} %#{rev}# {content}%*{right}%*")
+                    }
+                    TileStyle::Plain => format!("%#{rev}#{left}{content}{right}%*"),
+                    TileStyle::Bubble
+                    | TileStyle::Powerline
+                    | TileStyle::Slant
+                    | TileStyle::Block => {
+                        let content = if icon.is_empty() {
+                            content.to_string()
+                        } else {
+                            format!("{icon} {content}")
+                        };
+                        format!("%#{rev}#{left}%#{norm}#{content}%#{rev}#{right}%*")
+                    }
+                };
+                let width = status_width(&decorated)?;
+
+                Ok(match click_handler {
+                    Some(handler) if tile.clickable() => {
+                        (format!("%{id}@v:lua.{handler}@{decorated}%X"), width)
+                    }
+                    _ => (decorated, width),
+                })
+            };
+
+            Ok(Some(RenderedTile {
+                priority: tile.priority(),
+                full: decorate(&content)?,
+                compact: match tile.compact_content()? {
+                    Some(compact) if !compact.is_empty() => Some(decorate(&compact)?),
+                    _ => None,
+                },
+                compacted: false,
+                hidden: false,
+            }))
+        }
+
+        /// Render every tile of a section, reusing the cached render of the tiles that declare
+        /// [`Tile::events`] until one of the events fire. The tiles that aren't `visible` are
+        /// skipped and a failing tile is rendered as an error
+        /// marker until it is disabled for a while, see [`TileHealth`].
+        fn render_section(
+            section: &mut Tiles,
+            offset: usize,
+            click_handler: Option<&str>,
+            key: CacheKey,
+            look: (Option<TileStyle>, Option<Separators>),
+            visible: &dyn Fn(&dyn Tile, &str) -> bool,
+        ) -> nvim_oxi::Result<Vec<RenderedTile>> {
+            let mut rendered = vec![];
 
-                        format!(
-                            "%#{sep}#%#{norm}#{icon} %#{rev}# {content}%*%*",
-                            icon = tile.0.icon()?,
-                        )
+            for (i, tile) in section.iter_mut().enumerate() {
+                if tile.3.disabled() || !visible(&*tile.0, tile.4) {
+                    continue;
+                }
+                // a tile that couldn't be setup is setup again once it is enabled
+                if tile.3.needs_setup {
+                    Line::setup_tile(tile, look.0)?;
+                    if tile.3.needs_setup {
+                        continue;
                     }
+                }
+                PLAIN.set(TileStyle::resolve(look.0, &*tile.0) == TileStyle::Plain);
+                let cacheable = tile.0.events().is_some();
+
+                let result = match tile.2.get(key) {
+                    Some(cached) if cacheable => cached,
+                    _ => match render_tile(tile, offset + i, click_handler, key.1, look) {
+                        Ok(result) => {
+                            tile.3.failures = 0;
+                            if cacheable {
+                                tile.2.insert(key, result.clone());
+                            }
+                            result
+                        }
+                        // the failing render isn't cached so the tile is tried again next time
+                        Err(err) => {
+                            if tile.3.fail(tile.4, &err)? {
+                                let marker =
+                                    format!("%#StatusTileError#{}%*", glyph(icons::ERROR, "!"));
+                                let width = status_width(&marker)?;
+                                Some(RenderedTile {
+                                    priority: tile.0.priority(),
+                                    full: (marker, width),
+                                    compact: None,
+                                    compacted: false,
+                                    hidden: false,
+                                })
+                            } else {
+                                None
+                            }
+                        }
+                    },
                 };
 
-                sections.push(tile);
+                rendered.extend(result);
+            }
+
+            Ok(rendered)
+        }
+
+        /// Render the left, left center, center, right center and right sections.
+        fn render_sections(
+            sections: [&mut Tiles; 5],
+            (click_handler, key, look): (
+                Option<&str>,
+                CacheKey,
+                (Option<TileStyle>, Option<Separators>),
+            ),
+            visible: &dyn Fn(&dyn Tile, &str) -> bool,
+        ) -> nvim_oxi::Result<[Vec<RenderedTile>; 5]> {
+            let mut rendered: [Vec<RenderedTile>; 5] = Default::default();
+            let mut offset = 0;
+
+            for (section, output) in sections.into_iter().zip(&mut rendered) {
+                *output = render_section(section, offset, click_handler, key, look, visible)?;
+                offset += section.len();
             }
 
-            Ok(sections.join(" "))
+            Ok(rendered)
+        }
+
+        /// The total width needed to render all the section while keeping the center section at
+        /// the absolute center.
+        fn required_width(sections: &[Vec<RenderedTile>; 5]) -> usize {
+            let [left, lcent, cent, rcent, right] = sections.each_ref().map(|s| section_width(s));
+
+            let sides = if cent == 0 && lcent == 0 && rcent == 0 {
+                left + right
+            } else {
+                2 * left.max(right)
+            };
+
+            // the 2 spaces around the center section
+            sides + 2 * lcent.max(rcent) + cent + 2
+        }
+
+        /// Compact or else hide the visible tile with the lowest priority, the rightmost tile go
+        /// first on tie. Return `false` if every tile is already hidden.
+        fn shrink(sections: &mut [Vec<RenderedTile>; 5]) -> bool {
+            let Some(tile) = sections
+                .iter_mut()
+                .flatten()
+                .filter(|tile| !tile.hidden)
+                .rev()
+                .min_by_key(|tile| tile.priority)
+            else {
+                return false;
+            };
+
+            if tile.compact.is_some() && !tile.compacted {
+                tile.compacted = true;
+            } else {
+                tile.hidden = true;
+            }
+            true
+        }
+
+        /// Join the visible tiles of a section, returning the string and its width.
+        fn join_section(section: &[RenderedTile]) -> (String, usize) {
+            (
+                section
+                    .iter()
+                    .filter_map(RenderedTile::current)
+                    .map(|(tile, _)| tile.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                section_width(section),
+            )
+        }
+
+        fn section_width(section: &[RenderedTile]) -> usize {
+            let widths = section
+                .iter()
+                .filter_map(RenderedTile::current)
+                .map(|(_, width)| width)
+                .collect::<Vec<_>>();
+
+            widths.iter().copied().sum::<usize>() + widths.len().saturating_sub(1)
         }
 
         /// Pad the left and right section with space so that the center section is actually center
         /// align to the window.
-        #[allow(clippy::needless_pass_by_value)]
-        fn equalize(left: String, right: String) -> nvim_oxi::Result<(String, String)> {
-            // Janky fix for nvim_oxi bug:
-            // https://github.com/noib3/nvim-oxi/issues/267
-            // TODO: update nvim_oxi when this bug is fix
-            let (left_len, right_len) = (
-                if left.is_empty() {
-                    0
-                } else {
-                    eval_status(&left)?.width
-                },
-                if right.is_empty() {
-                    0
-                } else {
-                    eval_status(&right)?.width
-                },
-            );
-            Ok((
-                format!(
-                    "{}{}",
-                    left,
-                    " ".repeat(right_len.saturating_sub(left_len).try_into().unwrap())
-                ),
-                format!(
-                    "{}{}",
-                    " ".repeat(left_len.saturating_sub(right_len).try_into().unwrap()),
-                    right,
-                ),
-            ))
+        fn equalize(
+            (left, left_len): (String, usize),
+            (right, right_len): (String, usize),
+        ) -> (String, String) {
+            (
+                format!("{left}{}", " ".repeat(right_len.saturating_sub(left_len))),
+                format!("{}{right}", " ".repeat(left_len.saturating_sub(right_len))),
+            )
         }
 
         if self.not_setup {
             self.setup()?;
         }
 
-        if self
-            .exclude_ft
-            .contains(&get_option::<String>("ft")?.as_str())
-        {
+        let mut context = nvim().win_context()?;
+        context.width = self.available_width()?;
+        if self.hide_rules.iter().any(|rule| rule(&context)) {
             return Ok(String::new());
         }
+        let tile_rules = &self.tile_hide_rules;
+        let visible = |tile: &dyn Tile, name: &str| {
+            tile.visible(&context)
+                && !tile_rules
+                    .iter()
+                    .any(|(rule_name, rule)| *rule_name == name && rule(&context))
+        };
+
+        let handler = self.click_handler.as_deref();
+        // each window has its own cache, full width lines are the same for every window
+        let win = if self.full_width {
+            0
+        } else {
+            nvim().current_win()?
+        };
+        let key = (win, dim);
+        let look = (self.style, self.separators);
 
-        let (left, lcent, cent, rcent, right) = (
-            render_section(&mut self.left)?,
-            render_section(&mut self.left_center)?,
-            render_section(&mut self.center)?,
-            render_section(&mut self.right_center)?,
-            render_section(&mut self.right)?,
-        );
+        let mut sections = render_sections(
+            [
+                &mut self.left,
+                &mut self.left_center,
+                &mut self.center,
+                &mut self.right_center,
+                &mut self.right,
+            ],
+            (handler, key, look),
+            &|tile, name| visible(tile, name) && !tile.fills_line(),
+        )?;
 
-        let (left, right) = if cent.is_empty() && lcent.is_empty() && rcent.is_empty() {
-            (left, right)
+        // the tiles filling the line are rendered with the width left by the other tiles
+        let mut fills = false;
+        let remaining = context.width.saturating_sub(required_width(&sections) + 1);
+        for (tile, _, cache, _, name) in [
+            &mut self.left,
+            &mut self.left_center,
+            &mut self.center,
+            &mut self.right_center,
+            &mut self.right,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if tile.fills_line() && visible(&**tile, name) {
+                fills = true;
+                if tile.set_width(remaining) {
+                    cache.clear();
+                }
+            }
+        }
+        if fills {
+            sections = render_sections(
+                [
+                    &mut self.left,
+                    &mut self.left_center,
+                    &mut self.center,
+                    &mut self.right_center,
+                    &mut self.right,
+                ],
+                (handler, key, look),
+                &visible,
+            )?;
+        }
+
+        while required_width(&sections) > context.width && shrink(&mut sections) {}
+
+        let [left, lcent, cent, rcent, right] = sections.each_ref().map(|s| join_section(s));
+
+        let (left, right) = if cent.1 == 0 && lcent.1 == 0 && rcent.1 == 0 {
+            (left.0, right.0)
         } else {
-            equalize(left, right)?
+            equalize(left, right)
         };
         // fliped because the padding direction is reversed on center
-        let (rcent, lcent) = equalize(rcent, lcent)?;
+        let (rcent, lcent) = equalize(rcent, lcent);
+        let cent = cent.0;
 
         Ok(format!("{left}%={lcent} {cent} {rcent}%={right}",))
     }
 
+    /// The width the line can take, the whole editor for a full width line or else the window the
+    /// line is drawn for.
+    fn available_width(&self) -> nvim_oxi::Result<usize> {
+        let nvim = nvim();
+        if self.full_width {
+            nvim.columns()
+        } else {
+            nvim.win_width(nvim.status_window()?)
+        }
+    }
+
+    /// Render `line` instead of this line for the windows that aren't the current window.
+    ///
+    /// The tiles of `line` are not clickable.
+    pub fn set_inactive(&mut self, line: Line) {
+        self.inactive = Some(Box::new(line));
+    }
+
+    /// Render every tile with a dimmed gray style for the windows that aren't the current window.
+    pub fn dim_inactive(&mut self) {
+        self.dim_inactive = true;
+    }
+
+    /// Render every tile of this line with `style` instead of their own [`Tile::style`].
+    ///
+    /// Tiles without an icon keep their own style when `style` is [`TileStyle::Icon`].
+    pub fn set_style(&mut self, style: TileStyle) {
+        self.style = Some(style);
+    }
+
+    /// Draw `separators` around every tile of this line instead of the default separators of
+    /// their style.
+    pub fn set_separators(&mut self, separators: Separators) {
+        self.separators = Some(separators);
+    }
+
+    /// Mark this line as spanning the whole editor like the global statusline or the tabline
+    /// instead of a single window.
+    pub fn full_width(&mut self) {
+        self.full_width = true;
+    }
+
     /// Add a tile to the left section of this line.
     pub fn add_left<T>(&mut self, tile: T)
     where
         T: Tile + 'static,
     {
         let opt = tile.highlight_opt();
-        self.left.push((Box::new(tile), opt));
+        let name = tile.name();
+        self.left.push((
+            Box::new(tile),
+            opt,
+            TileCache::default(),
+            TileHealth::default(),
+            name,
+        ));
     }
 
     /// Add a tile to the left center section of this line.
@@ -400,7 +1367,14 @@ impl Line {
         T: Tile + 'static,
     {
         let opt = tile.highlight_opt();
-        self.left_center.push((Box::new(tile), opt));
+        let name = tile.name();
+        self.left_center.push((
+            Box::new(tile),
+            opt,
+            TileCache::default(),
+            TileHealth::default(),
+            name,
+        ));
     }
 
     /// Add a tile to the center section of this line.
@@ -411,7 +1385,14 @@ impl Line {
         T: Tile + 'static,
     {
         let opt = tile.highlight_opt();
-        self.center.push((Box::new(tile), opt));
+        let name = tile.name();
+        self.center.push((
+            Box::new(tile),
+            opt,
+            TileCache::default(),
+            TileHealth::default(),
+            name,
+        ));
     }
 
     /// Add a tile to the right center section of this line.
@@ -422,7 +1403,14 @@ impl Line {
         T: Tile + 'static,
     {
         let opt = tile.highlight_opt();
-        self.right_center.push((Box::new(tile), opt));
+        let name = tile.name();
+        self.right_center.push((
+            Box::new(tile),
+            opt,
+            TileCache::default(),
+            TileHealth::default(),
+            name,
+        ));
     }
 
     /// Add a tile to the right section of this line.
@@ -431,13 +1419,49 @@ impl Line {
         T: Tile + 'static,
     {
         let opt = tile.highlight_opt();
-        self.right.push((Box::new(tile), opt));
+        let name = tile.name();
+        self.right.push((
+            Box::new(tile),
+            opt,
+            TileCache::default(),
+            TileHealth::default(),
+            name,
+        ));
     }
 
     /// Add a file type to be excluded when rendering
     pub fn exclude_ft(&mut self, ft: &'static str) {
-        self.exclude_ft.push(ft);
+        self.hide_if(move |win| win.filetype == ft);
+    }
+
+    /// Render nothing in the windows where `rule` return `true`.
+    pub fn hide_if<F>(&mut self, rule: F)
+    where
+        F: Fn(&WinContext) -> bool + 'static,
+    {
+        self.hide_rules.push(Box::new(rule));
     }
+
+    /// Hide the tiles of this line named `name` in the windows where `rule` return `true`. The
+    /// tiles added by [`Line::from_layout`] are named like in the layout and the other tiles by
+    /// [`Tile::name`].
+    pub fn hide_tile_if<F>(&mut self, name: &'static str, rule: F)
+    where
+        F: Fn(&WinContext) -> bool + 'static,
+    {
+        self.tile_hide_rules.push((name, Box::new(rule)));
+    }
+}
+
+/// The window the line is being drawn for, `g:statusline_winid` is only set while evaluating
+/// `statusline` and `winbar` so the current window is used otherwise.
+pub fn status_window() -> nvim_oxi::Result<i64> {
+    nvim().status_window()
+}
+
+/// Helper to get the display width of a statusline string.
+pub fn status_width(str: &str) -> nvim_oxi::Result<usize> {
+    nvim().status_width(str)
 }
 
 /// Helper to evaluate a statusline string.
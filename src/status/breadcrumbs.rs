@@ -0,0 +1,386 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use mlua::{AnyUserData, ObjectLike, Table};
+
+use crate::{
+    autocmds::create_autocmd,
+    icons,
+    lsp::buffer_clients,
+    table,
+    theme::{Color::*, HighlightOpt},
+    vim, vim_fn,
+};
+
+use super::{STATUS_LINE_FG, Tile, glyph};
+
+/// Longest name shown for a symbol before it is truncated.
+const MAX_NAME_WIDTH: usize = 30;
+
+#[derive(Clone)]
+struct Symbol {
+    name: String,
+    kind: i64,
+    /// Start and end of the symbol, starting from 0.
+    range: ((i64, i64), (i64, i64)),
+    /// Where to jump when the symbol is clicked.
+    target: (i64, i64),
+}
+
+#[derive(Default)]
+struct BreadcrumbState {
+    /// Document symbols of each buffer along with the `changedtick` they were requested for.
+    symbols: HashMap<i64, (i64, Vec<Symbol>)>,
+    /// Buffers with a pending `textDocument/documentSymbol` request.
+    pending: HashSet<i64>,
+    /// Increased whenever new symbols arrive so the path is recomputed.
+    generation: u64,
+    /// Jump targets of the symbols rendered in each window.
+    targets: HashMap<i64, Vec<(i64, i64)>>,
+}
+
+/// The window, buffer, `changedtick`, cursor and symbols generation the path was computed for.
+type PathKey = (i64, i64, i64, (i64, i64), u64);
+
+/// Tile to show the path of symbols at the cursor like `module › impl › fn` with their kind icon.
+///
+/// The symbols come from `textDocument/documentSymbol` when a LSP server support it or else from
+/// the treesitter nodes around the cursor. The symbols are only requested again when the
+/// `changedtick` of the buffer change and are dropped with the buffer. Clicking a symbol jump to
+/// it.
+pub struct Breadcrumbs {
+    state: Rc<RefCell<BreadcrumbState>>,
+    key: Option<PathKey>,
+    path: Vec<Symbol>,
+}
+
+impl Breadcrumbs {
+    #[must_use]
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {
+            state: Rc::new(RefCell::new(BreadcrumbState::default())),
+            key: None,
+            path: vec![],
+        }
+    }
+
+    /// Request the document symbols of `buf` if they are outdated, return `false` if no LSP client
+    /// support document symbols.
+    fn request_symbols(&self, buf: i64, tick: i64) -> nvim_oxi::Result<bool> {
+        let lsp = vim()?.get::<Table>("lsp")?;
+
+        let Some(client) = buffer_clients(buf, Some("textDocument/documentSymbol"))?
+            .into_iter()
+            .next()
+        else {
+            return Ok(false);
+        };
+
+        let mut state = self.state.borrow_mut();
+        let outdated = state.symbols.get(&buf).is_none_or(|(old, _)| *old != tick);
+        if !outdated || !state.pending.insert(buf) {
+            return Ok(true);
+        }
+        drop(state);
+
+        let state = self.state.clone();
+        client.call_method::<mlua::Value>(
+            "request",
+            (
+                "textDocument/documentSymbol",
+                table! {
+                    textDocument = lsp
+                        .get::<Table>("util")?
+                        .call_function::<Table>("make_text_document_params", buf)?
+                },
+                nvim_oxi::mlua::lua().create_function(
+                    move |_, (err, result): (mlua::Value, Option<Table>)| {
+                        let mut state = state.borrow_mut();
+                        state.pending.remove(&buf);
+
+                        // an error is stored as no symbols so it isn't requested again until the
+                        // buffer change
+                        let mut symbols = vec![];
+                        if err.is_nil()
+                            && let Some(result) = result
+                        {
+                            collect_symbols(&result, &mut symbols)?;
+                        }
+                        state.symbols.insert(buf, (tick, symbols));
+                        state.generation += 1;
+                        drop(state);
+
+                        nvim_oxi::api::command("redrawstatus!").map_err(mlua::Error::external)
+                    },
+                )?,
+                buf,
+            ),
+        )?;
+
+        Ok(true)
+    }
+}
+
+impl Default for Breadcrumbs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tile for Breadcrumbs {
+    fn content(&self) -> nvim_oxi::Result<String> {
+        Ok(self
+            .path
+            .iter()
+            .enumerate()
+            .map(|(i, symbol)| {
+                let name = if symbol.name.chars().count() > MAX_NAME_WIDTH {
+                    format!(
+                        "{}…",
+                        symbol
+                            .name
+                            .chars()
+                            .take(MAX_NAME_WIDTH - 1)
+                            .collect::<String>()
+                    )
+                } else {
+                    symbol.name.clone()
+                };
+
+                // the click id start from 1 since `%0@` would be read as zero padding
+                format!(
+                    "%{}@v:lua.crabbyvim_breadcrumb_click@{}{}%X",
+                    i + 1,
+                    kind_prefix(symbol.kind),
+                    name.replace('%', "%%")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(&format!(" {} ", glyph(icons::BREADCRUMB, ">"))))
+    }
+
+    fn highlight_name(&self) -> nvim_oxi::Result<String> {
+        Ok("StatusBreadcrumbs".into())
+    }
+
+    fn priority(&self) -> u8 {
+        45
+    }
+
+    fn compact_content(&self) -> nvim_oxi::Result<Option<String>> {
+        Ok(self.path.last().map(|symbol| {
+            format!(
+                "{}{}",
+                kind_prefix(symbol.kind),
+                symbol.name.replace('%', "%%")
+            )
+        }))
+    }
+
+    fn highlight_opt(&self) -> HighlightOpt {
+        HighlightOpt::with_fg(White).bg(STATUS_LINE_FG)
+    }
+
+    fn setup(&self) -> nvim_oxi::Result<()> {
+        let state = self.state.clone();
+        let lua = nvim_oxi::mlua::lua();
+
+        lua.globals().set(
+            "crabbyvim_breadcrumb_click",
+            lua.create_function(move |_, (id, _, button): (usize, i64, String)| {
+                if button != "l" {
+                    return Ok(());
+                }
+
+                let win = vim_fn::<Table>("getmousepos", ())
+                    .map_err(mlua::Error::external)?
+                    .get::<i64>("winid")?;
+                let Some(&(line, col)) = state
+                    .borrow()
+                    .targets
+                    .get(&win)
+                    .and_then(|targets| targets.get(id.checked_sub(1)?))
+                else {
+                    return Ok(());
+                };
+
+                let api = vim().map_err(mlua::Error::external)?.get::<Table>("api")?;
+                api.call_function::<()>("nvim_set_current_win", win)?;
+                api.call_function::<()>("nvim_win_set_cursor", (win, [line + 1, col]))?;
+                Ok(())
+            })?,
+        )?;
+
+        let state = self.state.clone();
+        create_autocmd(&["BufDelete"], &["*"], move |args| {
+            state
+                .borrow_mut()
+                .symbols
+                .remove(&i64::from(args.buffer.handle()));
+            Ok(())
+        })?;
+
+        let state = self.state.clone();
+        create_autocmd(&["WinClosed"], &["*"], move |args| {
+            if let Ok(win) = args.r#match.parse::<i64>() {
+                state.borrow_mut().targets.remove(&win);
+            }
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    fn update(&mut self) -> nvim_oxi::Result<()> {
+        let api = vim()?.get::<Table>("api")?;
+        let win = api.call_function::<i64>("nvim_get_current_win", ())?;
+        let buf = api.call_function::<i64>("nvim_get_current_buf", ())?;
+        let tick = vim()?.get::<Table>("b")?.get::<i64>("changedtick")?;
+        let [line, col] = api.call_function::<[i64; 2]>("nvim_win_get_cursor", 0)?;
+        let cursor = (line - 1, col);
+
+        let has_lsp = self.request_symbols(buf, tick)?;
+
+        let key = (win, buf, tick, cursor, self.state.borrow().generation);
+        if self.key == Some(key) {
+            return Ok(());
+        }
+
+        self.path = match self.state.borrow().symbols.get(&buf) {
+            // the symbols of an older `changedtick` are used until the new ones arrive
+            Some((_, symbols)) if has_lsp => symbol_path(symbols, cursor),
+            _ => treesitter_path()?,
+        };
+        self.key = Some(key);
+
+        self.state
+            .borrow_mut()
+            .targets
+            .insert(win, self.path.iter().map(|symbol| symbol.target).collect());
+
+        Ok(())
+    }
+}
+
+/// Flatten the `DocumentSymbol` tree or the list of `SymbolInformation` into `out`.
+fn collect_symbols(symbols: &Table, out: &mut Vec<Symbol>) -> mlua::Result<()> {
+    fn position(position: &Table) -> mlua::Result<(i64, i64)> {
+        Ok((position.get("line")?, position.get("character")?))
+    }
+
+    for symbol in symbols.sequence_values::<Table>() {
+        let symbol = symbol?;
+        let range = match symbol.get::<Option<Table>>("range")? {
+            Some(range) => range,
+            None => symbol.get::<Table>("location")?.get::<Table>("range")?,
+        };
+        let target = symbol
+            .get::<Option<Table>>("selectionRange")?
+            .unwrap_or_else(|| range.clone());
+
+        out.push(Symbol {
+            name: symbol.get("name")?,
+            kind: symbol.get("kind")?,
+            range: (
+                position(&range.get("start")?)?,
+                position(&range.get("end")?)?,
+            ),
+            target: position(&target.get("start")?)?,
+        });
+
+        if let Some(children) = symbol.get::<Option<Table>>("children")? {
+            collect_symbols(&children, out)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The symbols containing `cursor` from the outermost to the innermost.
+fn symbol_path(symbols: &[Symbol], cursor: (i64, i64)) -> Vec<Symbol> {
+    let mut path = symbols
+        .iter()
+        .filter(|symbol| symbol.range.0 <= cursor && cursor <= symbol.range.1)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    path.sort_by(|a, b| a.range.0.cmp(&b.range.0).then(b.range.1.cmp(&a.range.1)));
+    path
+}
+
+/// The `SymbolKind` of a treesitter node type, only the definitions and declarations are kept.
+fn node_kind(node_type: &str) -> Option<i64> {
+    if !["_item", "_definition", "_declaration"]
+        .iter()
+        .any(|suffix| node_type.ends_with(suffix))
+    {
+        return None;
+    }
+
+    Some(match node_type {
+        t if t.contains("method") => 6,
+        t if t.contains("function") => 12,
+        t if t.contains("class") || t.contains("impl") => 5,
+        t if t.contains("struct") => 23,
+        t if t.contains("enum") => 10,
+        t if t.contains("trait") || t.contains("interface") => 11,
+        t if t.contains("mod") => 2,
+        _ => return None,
+    })
+}
+
+/// The symbols found by walking up the treesitter nodes from the cursor, empty if the buffer has
+/// no parser.
+fn treesitter_path() -> nvim_oxi::Result<Vec<Symbol>> {
+    let treesitter = vim()?.get::<Table>("treesitter")?;
+    let mut node = treesitter.call_function::<Option<AnyUserData>>("get_node", ())?;
+    let mut path = vec![];
+
+    while let Some(current) = node {
+        if let Some(kind) = node_kind(&current.call_method::<String>("type", ())?) {
+            let mut name = current
+                .call_method::<Vec<AnyUserData>>("field", "name")?
+                .into_iter()
+                .next();
+            // `impl` blocks only have a type
+            if name.is_none() {
+                name = current
+                    .call_method::<Vec<AnyUserData>>("field", "type")?
+                    .into_iter()
+                    .next();
+            }
+
+            if let Some(name) = name {
+                let (start_row, start_col, _) =
+                    current.call_method::<(i64, i64, i64)>("start", ())?;
+                let (end_row, end_col, _) = current.call_method::<(i64, i64, i64)>("end_", ())?;
+                let (row, col, _) = name.call_method::<(i64, i64, i64)>("start", ())?;
+
+                path.push(Symbol {
+                    name: treesitter.call_function::<String>("get_node_text", (name, 0))?,
+                    kind,
+                    range: ((start_row, start_col), (end_row, end_col)),
+                    target: (row, col),
+                });
+            }
+        }
+
+        node = current.call_method::<Option<AnyUserData>>("parent", ())?;
+    }
+
+    path.reverse();
+    Ok(path)
+}
+
+/// The icon of the symbol `kind` followed by a space, nothing with
+/// [`TileStyle::Plain`](super::TileStyle::Plain).
+fn kind_prefix(kind: i64) -> String {
+    match glyph(icons::symbol_kind(kind), "") {
+        "" => String::new(),
+        icon => format!("{icon} "),
+    }
+}
@@ -1,17 +1,27 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    path::Path,
+    rc::Rc,
+};
+
 use mlua::{ObjectLike, Table};
-use nvim_oxi::api::get_var;
+use nvim_oxi::api::types::LogLevel;
 
 use crate::{
+    autocmds::create_autocmd,
     diagnostic::DiagnosticSeverity,
-    icons, table,
+    icons,
+    options::get_option,
+    require, table,
     theme::{
         Color::{self, *},
-        HighlightOpt, set_hl,
+        HighlightOpt,
     },
-    vim,
+    vim, vim_fn, vim_notify,
 };
 
-use super::{STATUS_LINE_FG, Tile, TileStyle};
+use super::{STATUS_LINE_FG, Tile, TileStyle, glyph, nvim};
 
 /// Tile to show the current mode.
 ///
@@ -41,6 +51,18 @@ impl Tile for Mode {
         Ok("StatusMode".into())
     }
 
+    fn events(&self) -> Option<&'static [&'static str]> {
+        Some(&["ModeChanged"])
+    }
+
+    fn priority(&self) -> u8 {
+        100
+    }
+
+    fn compact_content(&self) -> nvim_oxi::Result<Option<String>> {
+        Ok(self.0.as_str().get(..1).map(Into::into))
+    }
+
     fn highlight_opt(&self) -> HighlightOpt {
         HighlightOpt::with_bg(Blue)
     }
@@ -57,7 +79,7 @@ impl Tile for Mode {
     }
 
     fn update(&mut self) -> nvim_oxi::Result<()> {
-        self.0 = crate::Mode::current_mode()?;
+        self.0 = nvim().mode()?;
         Ok(())
     }
 }
@@ -110,6 +132,21 @@ impl Tile for Cwd {
         Ok("StatusCwd".into())
     }
 
+    fn events(&self) -> Option<&'static [&'static str]> {
+        Some(&["DirChanged"])
+    }
+
+    fn priority(&self) -> u8 {
+        40
+    }
+
+    fn compact_content(&self) -> nvim_oxi::Result<Option<String>> {
+        let name = self.map_path().1;
+        Ok(std::path::Path::new(&name)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned()))
+    }
+
     fn highlight_opt(&self) -> HighlightOpt {
         HighlightOpt::with_bg(Yellow)
     }
@@ -126,6 +163,8 @@ impl Tile for Cwd {
 
 /// Tile to show the current git branch with the help of
 /// [`gitsigns.nvim`](https://github.com/lewis6991/gitsigns.nvim)
+///
+/// Clicking the tile open the branch picker.
 pub struct GitBranch;
 
 impl Tile for GitBranch {
@@ -138,19 +177,38 @@ impl Tile for GitBranch {
     }
 
     fn content(&self) -> nvim_oxi::Result<String> {
-        match get_var::<String>("gitsigns_head") {
-            Ok(head) => Ok(head),
-            _ => Ok(String::new()),
-        }
+        Ok(nvim().git_head().unwrap_or_default())
     }
 
     fn highlight_name(&self) -> nvim_oxi::Result<String> {
         Ok("StatusGit".into())
     }
 
+    fn events(&self) -> Option<&'static [&'static str]> {
+        Some(&["User GitSignsUpdate", "BufEnter"])
+    }
+
+    fn priority(&self) -> u8 {
+        50
+    }
+
+    fn compact_content(&self) -> nvim_oxi::Result<Option<String>> {
+        let head = self.content()?;
+        Ok((head.chars().count() > 12)
+            .then(|| format!("{}…", head.chars().take(11).collect::<String>())))
+    }
+
     fn highlight_opt(&self) -> HighlightOpt {
         HighlightOpt::with_bg(Orange)
     }
+
+    fn clickable(&self) -> bool {
+        true
+    }
+
+    fn on_click(&mut self, _clicks: i64, _button: &str, _modifiers: &str) -> nvim_oxi::Result<()> {
+        open_picker("git_branches")
+    }
 }
 
 /// Tile to show the current location within the current file.
@@ -163,6 +221,21 @@ impl Tile for Loc {
         Ok("%3.c:%-3.l".into())
     }
 
+    // the items are expanded by Neovim but their width, used to fit the line, follow the cursor
+    fn events(&self) -> Option<&'static [&'static str]> {
+        Some(&[
+            "CursorMoved",
+            "CursorMovedI",
+            "BufEnter",
+            "TextChanged",
+            "TextChangedI",
+        ])
+    }
+
+    fn priority(&self) -> u8 {
+        90
+    }
+
     fn highlight_name(&self) -> nvim_oxi::Result<String> {
         Mode(crate::Mode::Normal).highlight_name()
     }
@@ -172,7 +245,7 @@ impl Tile for Loc {
     }
 }
 
-/// Tile to show the current zoom level of `neovide_scale_factor`.
+/// Tile to show the current zoom level of `neovide_scale_factor`. Clicking the tile reset the zoom.
 ///
 /// A conditional include is recommended so the tile can also be use with terminal neovim:
 /// ```rust
@@ -202,9 +275,23 @@ impl Tile for Zoom {
         Ok("StatusZoom".into())
     }
 
+    fn priority(&self) -> u8 {
+        10
+    }
+
     fn highlight_opt(&self) -> HighlightOpt {
         HighlightOpt::with_bg(Yellow)
     }
+
+    fn clickable(&self) -> bool {
+        true
+    }
+
+    fn on_click(&mut self, _clicks: i64, _button: &str, _modifiers: &str) -> nvim_oxi::Result<()> {
+        nvim_oxi::api::set_var("neovide_scale_factor", 1.0)?;
+        nvim_oxi::api::command("redrawstatus")?;
+        Ok(())
+    }
 }
 
 #[derive(Default)]
@@ -225,7 +312,7 @@ impl DiagnosticCount {
 /// Tile to show the diagnostic information/count of the current file or globally.
 ///
 /// When in global mode the color of the icons also change based on how many error/warning are
-/// founds.
+/// founds. Clicking the tile open the diagnostics picker.
 pub struct Diagnostic(bool, DiagnosticCount);
 
 impl Diagnostic {
@@ -260,16 +347,19 @@ impl Tile for Diagnostic {
         } = self.1;
 
         if error > 0 {
-            out.push(format!("%#StatusError#{} {error}", icons::ERROR));
+            out.push(format!(
+                "%#StatusError#{} {error}",
+                glyph(icons::ERROR, "E")
+            ));
         }
         if warn > 0 {
-            out.push(format!("%#StatusWarn#{} {warn}", icons::WARN));
+            out.push(format!("%#StatusWarn#{} {warn}", glyph(icons::WARN, "W")));
         }
         if hint > 0 {
-            out.push(format!("%#StatusHint#{} {hint}", icons::HINT));
+            out.push(format!("%#StatusHint#{} {hint}", glyph(icons::HINT, "H")));
         }
         if info > 0 {
-            out.push(format!("%#StatusInfo#{} {info}", icons::INFO));
+            out.push(format!("%#StatusInfo#{} {info}", glyph(icons::INFO, "I")));
         }
 
         Ok(out.join(" "))
@@ -288,6 +378,14 @@ impl Tile for Diagnostic {
         HighlightOpt::with_bg(Red)
     }
 
+    fn events(&self) -> Option<&'static [&'static str]> {
+        Some(&["DiagnosticChanged", "BufEnter"])
+    }
+
+    fn priority(&self) -> u8 {
+        70
+    }
+
     fn update_highlight(&self, _old_opt: HighlightOpt) -> nvim_oxi::Result<HighlightOpt> {
         Ok(if self.0 {
             let total = self.1.total();
@@ -304,13 +402,14 @@ impl Tile for Diagnostic {
     }
 
     fn setup(&self) -> nvim_oxi::Result<()> {
-        set_hl("StatusError", HighlightOpt::with_fg(Red).bg(STATUS_LINE_FG))?;
-        set_hl(
+        let nvim = nvim();
+        nvim.set_hl("StatusError", HighlightOpt::with_fg(Red).bg(STATUS_LINE_FG))?;
+        nvim.set_hl(
             "StatusWarn",
             HighlightOpt::with_fg(Yellow).bg(STATUS_LINE_FG),
         )?;
-        set_hl("StatusInfo", HighlightOpt::with_fg(Blue).bg(STATUS_LINE_FG))?;
-        set_hl(
+        nvim.set_hl("StatusInfo", HighlightOpt::with_fg(Blue).bg(STATUS_LINE_FG))?;
+        nvim.set_hl(
             "StatusHint",
             HighlightOpt::with_fg(Purple).bg(STATUS_LINE_FG),
         )?;
@@ -318,22 +417,8 @@ impl Tile for Diagnostic {
     }
 
     fn update(&mut self) -> nvim_oxi::Result<()> {
-        let get_diagnostic = |severity: DiagnosticSeverity| -> nvim_oxi::Result<i64> {
-            Ok(vim()?
-                .get::<Table>("diagnostic")?
-                .call_function::<Table>(
-                    "get",
-                    (
-                        if self.0 {
-                            mlua::Nil
-                        } else {
-                            mlua::Value::Integer(0)
-                        },
-                        table! {severity = severity},
-                    ),
-                )?
-                .len()?)
-        };
+        let nvim = nvim();
+        let get_diagnostic = |severity: DiagnosticSeverity| nvim.diagnostic_count(severity, self.0);
 
         self.1 = DiagnosticCount {
             error: get_diagnostic(DiagnosticSeverity::Error)?,
@@ -344,4 +429,824 @@ impl Tile for Diagnostic {
 
         Ok(())
     }
+
+    fn clickable(&self) -> bool {
+        true
+    }
+
+    fn on_click(&mut self, _clicks: i64, _button: &str, _modifiers: &str) -> nvim_oxi::Result<()> {
+        open_picker(if self.0 {
+            "diagnostics"
+        } else {
+            "diagnostics_buffer"
+        })
+    }
+}
+
+/// Frames of the spinner shown by [`LspProgress`].
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// How often the spinner of [`LspProgress`] advance in milliseconds.
+const SPINNER_INTERVAL: i64 = 100;
+
+struct ProgressTask {
+    client_id: i64,
+    token: String,
+    client: String,
+    title: String,
+    message: Option<String>,
+    percentage: Option<f64>,
+}
+
+#[derive(Default)]
+struct ProgressState {
+    tasks: Vec<ProgressTask>,
+    frame: usize,
+    timer: Option<i64>,
+}
+
+/// Tile to show the progress of the LSP servers using the `LspProgress` event.
+///
+/// Only the most recently updated task is shown along with a spinner. The line is only redraw
+/// while a task is in progress and the tile disappear once every task has ended.
+pub struct LspProgress(Rc<RefCell<ProgressState>>);
+
+impl LspProgress {
+    #[must_use]
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self(Rc::new(RefCell::new(ProgressState::default())))
+    }
+
+    fn on_progress(state: &Rc<RefCell<ProgressState>>, args: &Table) -> nvim_oxi::Result<()> {
+        let data = args.get::<Table>("data")?;
+        let client_id = data.get::<i64>("client_id")?;
+        let params = data.get::<Table>("params")?;
+        // tokens can either be a string or an integer, mlua coerce both into a string.
+        let token = params.get::<String>("token")?;
+        let value = params.get::<Table>("value")?;
+
+        // partial results are also reported through `$/progress` but without a `kind`
+        let Some(kind) = value.get::<Option<String>>("kind")? else {
+            return Ok(());
+        };
+        let message = value.get::<Option<String>>("message")?;
+        let percentage = value.get::<Option<f64>>("percentage")?;
+
+        let index = state
+            .borrow()
+            .tasks
+            .iter()
+            .position(|task| task.client_id == client_id && task.token == token);
+
+        match (kind.as_str(), index) {
+            ("end", Some(index)) => {
+                state.borrow_mut().tasks.remove(index);
+            }
+            ("end", None) => {}
+            (_, Some(index)) => {
+                let mut state = state.borrow_mut();
+                let mut task = state.tasks.remove(index);
+                task.message = message.or(task.message);
+                task.percentage = percentage.or(task.percentage);
+                // the most recently updated task is the one shown
+                state.tasks.push(task);
+            }
+            (_, None) => {
+                let client = vim()?
+                    .get::<Table>("lsp")?
+                    .call_function::<Option<Table>>("get_client_by_id", client_id)?
+                    .map_or(Ok(String::new()), |client| client.get::<String>("name"))?;
+
+                state.borrow_mut().tasks.push(ProgressTask {
+                    client_id,
+                    token,
+                    client,
+                    title: value.get::<Option<String>>("title")?.unwrap_or_default(),
+                    message,
+                    percentage,
+                });
+            }
+        }
+
+        if state.borrow().tasks.is_empty() {
+            Self::stop_spinner(state)?;
+        } else {
+            Self::start_spinner(state)?;
+        }
+
+        nvim_oxi::api::command("redrawstatus")?;
+        Ok(())
+    }
+
+    fn start_spinner(state: &Rc<RefCell<ProgressState>>) -> nvim_oxi::Result<()> {
+        if state.borrow().timer.is_some() {
+            return Ok(());
+        }
+
+        let tick = state.clone();
+        let timer = vim_fn::<i64>(
+            "timer_start",
+            (
+                SPINNER_INTERVAL,
+                nvim_oxi::mlua::lua().create_function(move |_, _: mlua::Value| {
+                    tick.borrow_mut().frame += 1;
+                    if let Err(err) = nvim_oxi::api::command("redrawstatus") {
+                        vim_notify(&format!("Can't redraw statusline: {err}"), LogLevel::Error)
+                            .map_err(mlua::Error::external)?;
+                    }
+                    Ok(())
+                })?,
+                table! { ["repeat"] = -1 },
+            ),
+        )?;
+
+        state.borrow_mut().timer = Some(timer);
+        Ok(())
+    }
+
+    fn stop_spinner(state: &Rc<RefCell<ProgressState>>) -> nvim_oxi::Result<()> {
+        if let Some(timer) = state.borrow_mut().timer.take() {
+            vim_fn::<()>("timer_stop", timer)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for LspProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tile for LspProgress {
+    fn style(&self) -> TileStyle {
+        TileStyle::Icon
+    }
+
+    fn icon(&self) -> nvim_oxi::Result<String> {
+        Ok(SPINNER[self.0.borrow().frame % SPINNER.len()].into())
+    }
+
+    fn content(&self) -> nvim_oxi::Result<String> {
+        let state = self.0.borrow();
+        let Some(task) = state.tasks.last() else {
+            return Ok(String::new());
+        };
+
+        let mut out = vec![task.client.clone(), task.title.clone()];
+
+        if let Some(message) = &task.message {
+            out.push(message.clone());
+        }
+        if let Some(percentage) = task.percentage {
+            out.push(format!("{percentage:.0}%"));
+        }
+
+        Ok(out.join(" ").replace('%', "%%"))
+    }
+
+    fn highlight_name(&self) -> nvim_oxi::Result<String> {
+        Ok("StatusLspProgress".into())
+    }
+
+    fn priority(&self) -> u8 {
+        30
+    }
+
+    fn compact_content(&self) -> nvim_oxi::Result<Option<String>> {
+        Ok(self
+            .0
+            .borrow()
+            .tasks
+            .last()
+            .map(|task| match task.percentage {
+                Some(percentage) => format!("{percentage:.0}%%"),
+                None => task.client.clone(),
+            }))
+    }
+
+    fn highlight_opt(&self) -> HighlightOpt {
+        HighlightOpt::with_bg(Green)
+    }
+
+    fn setup(&self) -> nvim_oxi::Result<()> {
+        let state = self.0.clone();
+
+        vim()?.get::<Table>("api")?.call_function::<i64>(
+            "nvim_create_autocmd",
+            (
+                "LspProgress",
+                table! {
+                    callback = nvim_oxi::mlua::lua().create_function(move |_, args: Table| {
+                        if let Err(err) = Self::on_progress(&state, &args) {
+                            vim_notify(&format!("Can't update LSP progress: {err}"), LogLevel::Error)
+                                .map_err(mlua::Error::external)?;
+                        }
+                        Ok(())
+                    })?
+                },
+            ),
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Tile to show the register a macro is being recorded into, hidden when not recording.
+pub struct Recording(String);
+
+impl Recording {
+    #[must_use]
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self(String::new())
+    }
+}
+
+impl Default for Recording {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tile for Recording {
+    fn style(&self) -> TileStyle {
+        TileStyle::Icon
+    }
+
+    fn icon(&self) -> nvim_oxi::Result<String> {
+        Ok(icons::RECORDING.into())
+    }
+
+    fn content(&self) -> nvim_oxi::Result<String> {
+        if self.0.is_empty() {
+            return Ok(String::new());
+        }
+        Ok(format!("@{}", self.0))
+    }
+
+    fn highlight_name(&self) -> nvim_oxi::Result<String> {
+        Ok("StatusRecording".into())
+    }
+
+    fn events(&self) -> Option<&'static [&'static str]> {
+        Some(&["RecordingEnter", "RecordingLeave"])
+    }
+
+    fn priority(&self) -> u8 {
+        95
+    }
+
+    fn highlight_opt(&self) -> HighlightOpt {
+        HighlightOpt::with_bg(Red)
+    }
+
+    fn setup(&self) -> nvim_oxi::Result<()> {
+        // `reg_recording()` still return the register during `RecordingLeave` so the redraw is
+        // done after the event
+        create_autocmd(&["RecordingEnter", "RecordingLeave"], &["*"], |_| {
+            let vim = vim()?;
+            vim.call_function::<()>(
+                "schedule",
+                vim.get::<Table>("cmd")?
+                    .get::<mlua::Function>("redrawstatus")?,
+            )?;
+            Ok(())
+        })
+    }
+
+    fn update(&mut self) -> nvim_oxi::Result<()> {
+        self.0 = vim_fn::<String>("reg_recording", ())?;
+        Ok(())
+    }
+}
+
+/// Timeout of `searchcount()` in milliseconds so the tile never block the redraw in large files.
+const SEARCH_TIMEOUT: i64 = 50;
+
+/// Matches counted by `searchcount()` before giving up.
+const SEARCH_MAX_COUNT: i64 = 999;
+
+/// The search pattern, buffer, `changedtick` and cursor position the count was computed for.
+type SearchKey = (String, i64, i64, Vec<i64>);
+
+/// Tile to show the index of the match under the cursor and the total number of matches of the
+/// last search, hidden when the search isn't highlighted.
+///
+/// The count is only recomputed when the pattern, the buffer or the cursor change.
+pub struct SearchCount {
+    key: Option<SearchKey>,
+    count: String,
+}
+
+impl SearchCount {
+    #[must_use]
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {
+            key: None,
+            count: String::new(),
+        }
+    }
+}
+
+impl Default for SearchCount {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tile for SearchCount {
+    fn style(&self) -> TileStyle {
+        TileStyle::Icon
+    }
+
+    fn icon(&self) -> nvim_oxi::Result<String> {
+        Ok(icons::MAGNIFYING_GLASS.into())
+    }
+
+    fn content(&self) -> nvim_oxi::Result<String> {
+        Ok(self.count.clone())
+    }
+
+    fn highlight_name(&self) -> nvim_oxi::Result<String> {
+        Ok("StatusSearchCount".into())
+    }
+
+    fn priority(&self) -> u8 {
+        60
+    }
+
+    fn highlight_opt(&self) -> HighlightOpt {
+        HighlightOpt::with_bg(Cyan)
+    }
+
+    fn setup(&self) -> nvim_oxi::Result<()> {
+        // `:nohlsearch` doesn't redraw the statusline and only reset `v:hlsearch` once the command
+        // is executed so the redraw is done after `CmdlineLeave`
+        create_autocmd(&["CmdlineLeave"], &[":"], |_| {
+            let vim = vim()?;
+            vim.call_function::<()>(
+                "schedule",
+                vim.get::<Table>("cmd")?
+                    .get::<mlua::Function>("redrawstatus")?,
+            )?;
+            Ok(())
+        })
+    }
+
+    fn update(&mut self) -> nvim_oxi::Result<()> {
+        let vim = vim()?;
+        let pattern = vim_fn::<String>("getreg", "/")?;
+
+        if vim.get::<Table>("v")?.get::<i64>("hlsearch")? == 0 || pattern.is_empty() {
+            self.key = None;
+            self.count.clear();
+            return Ok(());
+        }
+
+        let key = (
+            pattern,
+            vim_fn::<i64>("bufnr", ())?,
+            vim.get::<Table>("b")?.get::<i64>("changedtick")?,
+            vim_fn::<Vec<i64>>("getcurpos", ())?,
+        );
+        if self.key.as_ref() == Some(&key) {
+            return Ok(());
+        }
+
+        let result = vim_fn::<Table>(
+            "searchcount",
+            table! {
+                recompute = true,
+                maxcount = SEARCH_MAX_COUNT,
+                timeout = SEARCH_TIMEOUT
+            },
+        )?;
+        let (current, total) = (result.get::<i64>("current")?, result.get::<i64>("total")?);
+        let over = |count: i64| {
+            if count > SEARCH_MAX_COUNT {
+                format!(">{SEARCH_MAX_COUNT}")
+            } else {
+                count.to_string()
+            }
+        };
+
+        self.count = match result.get::<i64>("incomplete")? {
+            // timed out
+            1 => "?/?".into(),
+            _ => format!("{}/{}", over(current), over(total)),
+        };
+        self.key = Some(key);
+
+        Ok(())
+    }
+}
+
+/// Tile to show the size of the selection in visual mode: the lines and characters in charwise
+/// visual, the lines in linewise visual and the dimensions of the block in blockwise visual.
+pub struct Selection {
+    full: String,
+    compact: String,
+}
+
+impl Selection {
+    #[must_use]
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {
+            full: String::new(),
+            compact: String::new(),
+        }
+    }
+}
+
+impl Default for Selection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tile for Selection {
+    fn style(&self) -> TileStyle {
+        TileStyle::Icon
+    }
+
+    fn icon(&self) -> nvim_oxi::Result<String> {
+        Ok(icons::SELECTION.into())
+    }
+
+    fn content(&self) -> nvim_oxi::Result<String> {
+        Ok(self.full.clone())
+    }
+
+    fn highlight_name(&self) -> nvim_oxi::Result<String> {
+        Ok("StatusSelection".into())
+    }
+
+    fn priority(&self) -> u8 {
+        70
+    }
+
+    fn compact_content(&self) -> nvim_oxi::Result<Option<String>> {
+        Ok(Some(self.compact.clone()))
+    }
+
+    fn highlight_opt(&self) -> HighlightOpt {
+        HighlightOpt::with_bg(Purple)
+    }
+
+    fn update(&mut self) -> nvim_oxi::Result<()> {
+        let plural =
+            |count: i64, word: &str| format!("{count} {word}{}", if count == 1 { "" } else { "s" });
+
+        let mode = vim_fn::<String>("mode", ())?;
+        if !matches!(mode.as_str(), "v" | "V" | "\u{16}") {
+            self.full.clear();
+            self.compact.clear();
+            return Ok(());
+        }
+
+        let lines = (vim_fn::<i64>("line", "v")? - vim_fn::<i64>("line", ".")?).abs() + 1;
+
+        (self.full, self.compact) = match mode.as_str() {
+            "v" => {
+                let chars = vim_fn::<Table>("wordcount", ())?.get::<i64>("visual_chars")?;
+                (
+                    format!("{} {}", plural(lines, "line"), plural(chars, "char")),
+                    format!("{lines}L {chars}C"),
+                )
+            }
+            "V" => (plural(lines, "line"), format!("{lines}L")),
+            _ => {
+                let columns =
+                    (vim_fn::<i64>("virtcol", "v")? - vim_fn::<i64>("virtcol", ".")?).abs() + 1;
+                let block = format!("{lines}x{columns}");
+                (block.clone(), block)
+            }
+        };
+
+        Ok(())
+    }
+}
+
+/// The git information of a repository shown by [`GitSync`].
+#[derive(Clone, Default)]
+struct RepoStatus {
+    ahead: i64,
+    behind: i64,
+    stash: i64,
+    /// The merge, rebase, etc. in progress.
+    operation: Option<&'static str>,
+}
+
+#[derive(Default)]
+struct GitState {
+    /// The repository root of each directory, the directories outside of a repository aren't
+    /// cached since a repository can be created in them later.
+    roots: HashMap<String, String>,
+    git_dirs: HashMap<String, String>,
+    statuses: HashMap<String, RepoStatus>,
+    /// Directories with a running `git rev-parse` job.
+    resolving: HashSet<String>,
+    /// Repositories with a running `git` job.
+    running: HashSet<String>,
+}
+
+/// Tile to show the commits ahead and behind the upstream branch, the number of stashes and the
+/// merge or rebase in progress of the current repository.
+///
+/// The information is computed with `git` in background jobs on `BufEnter`, `FocusGained` and
+/// `GitSignsUpdate` then cached per repository so rendering never wait for `git`. The tile is
+/// hidden when there is nothing to show.
+pub struct GitSync {
+    state: Rc<RefCell<GitState>>,
+    status: Option<RepoStatus>,
+}
+
+impl GitSync {
+    #[must_use]
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {
+            state: Rc::new(RefCell::new(GitState::default())),
+            status: None,
+        }
+    }
+
+    /// Start the jobs to refresh the status of the repository of the current buffer.
+    fn refresh(state: &Rc<RefCell<GitState>>) -> nvim_oxi::Result<()> {
+        let dir = buffer_dir()?;
+        let root = state.borrow().roots.get(&dir).cloned();
+
+        if let Some(root) = root {
+            return Self::fetch(state, root);
+        }
+        if !state.borrow_mut().resolving.insert(dir.clone()) {
+            return Ok(());
+        }
+
+        let (job_state, job_dir) = (state.clone(), dir.clone());
+        spawn(
+            &["git", "rev-parse", "--show-toplevel", "--absolute-git-dir"],
+            &dir,
+            move |output| {
+                let mut borrow = job_state.borrow_mut();
+                borrow.resolving.remove(&job_dir);
+                // not in a repository
+                let Some([root, git_dir, ..]) = output.as_deref() else {
+                    return Ok(());
+                };
+                borrow.roots.insert(job_dir.clone(), root.clone());
+                borrow.git_dirs.insert(root.clone(), git_dir.clone());
+                drop(borrow);
+
+                Self::fetch(&job_state, root.clone())
+            },
+        )
+        .inspect_err(|_| {
+            state.borrow_mut().resolving.remove(&dir);
+        })
+    }
+
+    /// Compute the status of the repository at `root`, unless a job is already running for it.
+    fn fetch(state: &Rc<RefCell<GitState>>, root: String) -> nvim_oxi::Result<()> {
+        if !state.borrow_mut().running.insert(root.clone()) {
+            return Ok(());
+        }
+
+        // the repository is released when a job can't be started so it is fetched next time
+        Self::fetch_status(state, root.clone()).inspect_err(|_| {
+            state.borrow_mut().running.remove(&root);
+        })
+    }
+
+    /// Start the jobs computing the status of the repository at `root`.
+    fn fetch_status(state: &Rc<RefCell<GitState>>, root: String) -> nvim_oxi::Result<()> {
+        let state = state.clone();
+        spawn(
+            &[
+                "git",
+                "rev-list",
+                "--left-right",
+                "--count",
+                "@{upstream}...HEAD",
+            ],
+            &root.clone(),
+            move |output| {
+                // no upstream branch
+                let (behind, ahead) = output
+                    .as_ref()
+                    .and_then(|lines| lines.first())
+                    .and_then(|line| line.split_once('\t'))
+                    .map_or((0, 0), |(behind, ahead)| {
+                        (behind.parse().unwrap_or(0), ahead.parse().unwrap_or(0))
+                    });
+
+                let (job_state, job_root) = (state.clone(), root.clone());
+                spawn(
+                    &["git", "rev-list", "--walk-reflogs", "--count", "refs/stash"],
+                    &root,
+                    move |output| {
+                        // no stash
+                        let stash = output
+                            .as_ref()
+                            .and_then(|lines| lines.first())
+                            .and_then(|line| line.parse().ok())
+                            .unwrap_or(0);
+
+                        let mut borrow = job_state.borrow_mut();
+                        let operation = borrow
+                            .git_dirs
+                            .get(&job_root)
+                            .and_then(|git_dir| git_operation(Path::new(git_dir)));
+                        borrow.statuses.insert(
+                            job_root.clone(),
+                            RepoStatus {
+                                ahead,
+                                behind,
+                                stash,
+                                operation,
+                            },
+                        );
+                        borrow.running.remove(&job_root);
+                        drop(borrow);
+
+                        // let the status line know the status changed
+                        vim()?.get::<Table>("api")?.call_function::<()>(
+                            "nvim_exec_autocmds",
+                            ("User", table! { pattern = "CrabbyGitSync" }),
+                        )?;
+                        nvim_oxi::api::command("redrawstatus")?;
+                        Ok(())
+                    },
+                )
+                .inspect_err(|_| {
+                    state.borrow_mut().running.remove(&root);
+                })
+            },
+        )
+    }
+}
+
+impl Default for GitSync {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tile for GitSync {
+    fn content(&self) -> nvim_oxi::Result<String> {
+        let Some(status) = &self.status else {
+            return Ok(String::new());
+        };
+
+        let mut out = vec![];
+
+        if status.ahead > 0 {
+            out.push(format!("{}{}", glyph(icons::GIT_AHEAD, "^"), status.ahead));
+        }
+        if status.behind > 0 {
+            out.push(format!(
+                "{}{}",
+                glyph(icons::GIT_BEHIND, "v"),
+                status.behind
+            ));
+        }
+        if status.stash > 0 {
+            out.push(format!(
+                "{} {}",
+                glyph(icons::GIT_STASH, "stash"),
+                status.stash
+            ));
+        }
+        if let Some(operation) = status.operation {
+            out.push(operation.to_uppercase());
+        }
+
+        Ok(out.join(" "))
+    }
+
+    fn highlight_name(&self) -> nvim_oxi::Result<String> {
+        Ok("StatusGitSync".into())
+    }
+
+    fn events(&self) -> Option<&'static [&'static str]> {
+        Some(&["BufEnter", "User CrabbyGitSync"])
+    }
+
+    fn priority(&self) -> u8 {
+        35
+    }
+
+    fn highlight_opt(&self) -> HighlightOpt {
+        HighlightOpt::with_bg(Orange)
+    }
+
+    fn update_highlight(&self, old_opt: HighlightOpt) -> nvim_oxi::Result<HighlightOpt> {
+        Ok(old_opt.bg(
+            match self.status.as_ref().and_then(|status| status.operation) {
+                Some(_) => Red,
+                None => Orange,
+            },
+        ))
+    }
+
+    fn setup(&self) -> nvim_oxi::Result<()> {
+        let state = self.state.clone();
+        create_autocmd(&["BufEnter", "FocusGained"], &["*"], move |_| {
+            Self::refresh(&state)
+        })?;
+
+        let state = self.state.clone();
+        create_autocmd(&["User"], &["GitSignsUpdate"], move |_| {
+            Self::refresh(&state)
+        })?;
+
+        Ok(())
+    }
+
+    fn update(&mut self) -> nvim_oxi::Result<()> {
+        let state = self.state.borrow();
+        self.status = state
+            .roots
+            .get(&buffer_dir()?)
+            .and_then(|root| state.statuses.get(root).cloned());
+        Ok(())
+    }
+}
+
+/// The directory of the current file or the current working directory for buffers without a file.
+fn buffer_dir() -> nvim_oxi::Result<String> {
+    let dir = vim_fn::<String>("expand", "%:p:h")?;
+    if get_option::<String>("buftype")?.is_empty()
+        && vim_fn::<i32>("isdirectory", dir.as_str())? == 1
+    {
+        Ok(dir)
+    } else {
+        vim_fn::<String>("getcwd", ())
+    }
+}
+
+/// The operation in progress in the repository with the `.git` directory `git_dir`.
+fn git_operation(git_dir: &Path) -> Option<&'static str> {
+    [
+        ("MERGE_HEAD", "merge"),
+        ("rebase-merge", "rebase"),
+        ("rebase-apply", "rebase"),
+        ("CHERRY_PICK_HEAD", "cherry-pick"),
+        ("REVERT_HEAD", "revert"),
+        ("BISECT_LOG", "bisect"),
+    ]
+    .into_iter()
+    .find(|(file, _)| git_dir.join(file).exists())
+    .map(|(_, operation)| operation)
+}
+
+/// Run `cmd` in `cwd` as a background job and call `on_exit` with its output, `None` if the command
+/// failed. Return an error if the job can't be started.
+fn spawn(
+    cmd: &[&str],
+    cwd: &str,
+    on_exit: impl Fn(Option<Vec<String>>) -> nvim_oxi::Result<()> + 'static,
+) -> nvim_oxi::Result<()> {
+    let lua = nvim_oxi::mlua::lua();
+    let output = Rc::new(RefCell::new(vec![]));
+    let stdout = output.clone();
+
+    let job = vim_fn::<i64>(
+        "jobstart",
+        (
+            cmd.to_vec(),
+            table! {
+                cwd = cwd,
+                stdout_buffered = true,
+                on_stdout = lua.create_function(move |_, (_, data): (i64, Vec<String>)| {
+                    *stdout.borrow_mut() = data;
+                    Ok(())
+                })?,
+                on_exit = lua.create_function(move |_, (_, code): (i64, i64)| {
+                    let mut lines = output.take();
+                    // the output always end with an empty line
+                    lines.retain(|line| !line.is_empty());
+                    on_exit((code == 0).then_some(lines)).map_err(mlua::Error::external)
+                })?
+            },
+        ),
+    )?;
+
+    // 0 for invalid arguments and -1 when the command isn't executable
+    if job <= 0 {
+        return Err(mlua::Error::RuntimeError(format!("Can't start `{}`", cmd.join(" "))).into());
+    }
+    Ok(())
+}
+
+/// Open the `snacks.nvim` picker with the given `name`.
+fn open_picker(name: &str) -> nvim_oxi::Result<()> {
+    require("snacks")?
+        .get::<Table>("picker")?
+        .call_function::<()>(name, table! {})?;
+    Ok(())
 }
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use super::*;
+
+/// Function creating a new instance of a tile.
+pub type TileFactory = Box<dyn Fn() -> Box<dyn Tile>>;
+
+/// Tiles that can be used in a layout string by [`Line::from_layout`], keyed by name.
+///
+/// The default registry contain every tile of this module, new tiles can be added with
+/// [`TileRegistry::register`].
+pub struct TileRegistry(HashMap<&'static str, TileFactory>);
+
+impl TileRegistry {
+    /// Create an empty registry.
+    #[must_use]
+    pub fn new() -> Self {
+        Self(HashMap::new())
+    }
+
+    /// Register the tile created by `factory` under `name`, replacing any tile with the same name.
+    pub fn register<T, F>(&mut self, name: &'static str, factory: F)
+    where
+        T: Tile + 'static,
+        F: Fn() -> T + 'static,
+    {
+        self.0.insert(name, Box::new(move || Box::new(factory())));
+    }
+
+    /// Create a new instance of the tile registered as `name`, along with the registered name.
+    #[must_use]
+    pub fn create(&self, name: &str) -> Option<(&'static str, Box<dyn Tile>)> {
+        self.0
+            .get_key_value(name)
+            .map(|(name, factory)| (*name, factory()))
+    }
+}
+
+impl Default for TileRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+
+        registry.register("mode", Mode::new);
+        registry.register("recording", Recording::new);
+        registry.register("cwd", Cwd::new);
+        registry.register("git_branch", || GitBranch);
+        registry.register("git_sync", GitSync::new);
+        registry.register("diagnostics", || Diagnostic::new(true));
+        registry.register("lsp_progress", LspProgress::new);
+        registry.register("zoom", || Zoom);
+        registry.register("search_count", SearchCount::new);
+        registry.register("selection", Selection::new);
+        registry.register("loc", || Loc);
+
+        registry.register("git_diff", || GitDiff);
+        registry.register("buffer_diagnostics", || Diagnostic::new(false));
+        registry.register("word_count", WordCount::new);
+        registry.register("filename", FileName::new);
+        registry.register("alt_filename", AltFileName::new);
+        registry.register("breadcrumbs", Breadcrumbs::new);
+        registry.register("inlay_hints", InlayHints::new);
+        registry.register("lsp", Lsp::new);
+        registry.register("formatter", Formatter::new);
+        registry.register("tools", Tools::new);
+        registry.register("file_format", FileFormat::new);
+        registry.register("file_status", FileStatus::new);
+
+        registry.register("buffers", BufferList::new);
+        registry.register("tabs", TabList::new);
+
+        registry
+    }
+}
+
+impl Line {
+    /// Create a line from a layout string of tile names from `registry` separated by space.
+    ///
+    /// Tiles go to the left section until a separator move to the next section:
+    /// - `<` start the left center section.
+    /// - The first `|` start the center section and the second `|` the right center section.
+    /// - `>` start the right section.
+    ///
+    /// For example `mode cwd git_branch | filename | tools file_status > loc` put `mode`, `cwd`
+    /// and `git_branch` on the left, `filename` in the center, `tools` and `file_status` right of
+    /// the center and `loc` on the right.
+    pub fn from_layout(layout: &str, registry: &TileRegistry) -> nvim_oxi::Result<Line> {
+        let error = |message: String| -> nvim_oxi::Error {
+            mlua::Error::RuntimeError(format!("Invalid layout `{layout}`: {message}")).into()
+        };
+
+        let mut line = Line::new();
+        // index of the current section in `Line::sections_mut`
+        let mut section = 0;
+
+        let spaced = layout
+            .replace('<', " < ")
+            .replace('|', " | ")
+            .replace('>', " > ");
+        for token in spaced.split_whitespace() {
+            section = match (token, section) {
+                ("<", 0) => 1,
+                ("|", 0 | 1) => 2,
+                ("|", 2) => 3,
+                (">", 0..=3) => 4,
+                ("<" | "|" | ">", _) => return Err(error(format!("unexpected `{token}`"))),
+                (name, _) => {
+                    let (name, tile) = registry
+                        .create(name)
+                        .ok_or_else(|| error(format!("unknown tile `{name}`")))?;
+                    let opt = tile.highlight_opt();
+                    line.sections_mut()[section].push((
+                        tile,
+                        opt,
+                        TileCache::default(),
+                        TileHealth::default(),
+                        name,
+                    ));
+                    continue;
+                }
+            };
+        }
+
+        Ok(line)
+    }
+}
@@ -1,16 +1,19 @@
+use std::collections::HashMap;
+
 use mlua::{ObjectLike, Table};
-use nvim_oxi::{Dictionary, api::Buffer};
+use nvim_oxi::api::Buffer;
 
 use crate::{
     icons,
+    lsp::{buffer_clients, inlay_hints_enabled},
     options::get_option,
     plugins::devicons::get_icon,
     require, table,
-    theme::{Color::*, HighlightOpt, get_hl, set_hl},
+    theme::{Color::*, HighlightOpt},
     vim, vim_fn,
 };
 
-use super::{STATUS_LINE_FG, Tile, TileStyle, eval_status};
+use super::{STATUS_LINE_FG, Tile, TileStyle, WinContext, eval_status, glyph, nvim};
 
 /// Tile to show the git diff of the current file with the help of
 /// [`gitsigns.nvim`](https://github.com/lewis6991/gitsigns.nvim)
@@ -26,18 +29,7 @@ impl Tile for GitDiff {
     }
 
     fn content(&self) -> nvim_oxi::Result<String> {
-        let (added, changed, removed) =
-            match Buffer::current().get_var::<Dictionary>("gitsigns_status_dict") {
-                Ok(dict) => (
-                    dict.get("added")
-                        .map_or(0, |o| unsafe { o.as_integer_unchecked() }),
-                    dict.get("changed")
-                        .map_or(0, |o| unsafe { o.as_integer_unchecked() }),
-                    dict.get("removed")
-                        .map_or(0, |o| unsafe { o.as_integer_unchecked() }),
-                ),
-                Err(_) => (0, 0, 0),
-            };
+        let (added, changed, removed) = nvim().git_diff();
 
         let mut out = vec![];
 
@@ -58,20 +50,29 @@ impl Tile for GitDiff {
         Ok("StatusGitDiff".into())
     }
 
+    fn events(&self) -> Option<&'static [&'static str]> {
+        Some(&["User GitSignsUpdate", "BufEnter"])
+    }
+
+    fn priority(&self) -> u8 {
+        40
+    }
+
     fn highlight_opt(&self) -> HighlightOpt {
         HighlightOpt::with_bg(Orange)
     }
 
     fn setup(&self) -> nvim_oxi::Result<()> {
-        set_hl(
+        let nvim = nvim();
+        nvim.set_hl(
             "StatusGitAdd",
             HighlightOpt::with_fg(Green).bg(STATUS_LINE_FG),
         )?;
-        set_hl(
+        nvim.set_hl(
             "StatusGitChange",
             HighlightOpt::with_fg(Yellow).bg(STATUS_LINE_FG),
         )?;
-        set_hl(
+        nvim.set_hl(
             "StatusGitRemove",
             HighlightOpt::with_fg(Red).bg(STATUS_LINE_FG),
         )?;
@@ -115,6 +116,14 @@ impl Tile for FileName {
         Ok(format!("Status{}", get_icon(&self.0)?.1))
     }
 
+    fn events(&self) -> Option<&'static [&'static str]> {
+        Some(&["BufEnter", "BufFilePost", "BufWritePost", "FileType"])
+    }
+
+    fn priority(&self) -> u8 {
+        100
+    }
+
     fn highlight_opt(&self) -> HighlightOpt {
         HighlightOpt::with_bg(Blue)
     }
@@ -125,13 +134,14 @@ impl Tile for FileName {
     }
 
     fn update_highlight(&self, _old_opt: HighlightOpt) -> nvim_oxi::Result<HighlightOpt> {
-        Ok(get_hl(get_icon(&self.0)?.1)?.reverse_fg_bg())
+        Ok(nvim().get_hl(&get_icon(&self.0)?.1)?.reverse_fg_bg())
     }
 }
 
 /// Tile to show the alternate file name.
 ///
-/// The tile also change the color to match the file type using `nvim-web-devicons`.
+/// The tile also change the color to match the file type using `nvim-web-devicons`. The tile is
+/// hidden in narrow windows.
 pub struct AltFileName(String);
 
 impl AltFileName {
@@ -148,6 +158,9 @@ impl Default for AltFileName {
     }
 }
 
+/// Narrowest window where [`AltFileName`] is shown.
+const ALT_FILE_NAME_MIN_WIDTH: usize = 80;
+
 impl Tile for AltFileName {
     fn content(&self) -> nvim_oxi::Result<String> {
         let file_name = self.0.clone();
@@ -162,6 +175,18 @@ impl Tile for AltFileName {
         Ok(format!("StatusAlt{}", get_icon(&self.0)?.1))
     }
 
+    fn events(&self) -> Option<&'static [&'static str]> {
+        Some(&["BufEnter"])
+    }
+
+    fn priority(&self) -> u8 {
+        20
+    }
+
+    fn compact_content(&self) -> nvim_oxi::Result<Option<String>> {
+        Ok(Some(self.0.clone()))
+    }
+
     fn highlight_opt(&self) -> HighlightOpt {
         HighlightOpt::with_bg(Blue)
     }
@@ -172,7 +197,11 @@ impl Tile for AltFileName {
     }
 
     fn update_highlight(&self, _old_opt: HighlightOpt) -> nvim_oxi::Result<HighlightOpt> {
-        Ok(get_hl(get_icon(&self.0)?.1)?.reverse_fg_bg())
+        Ok(nvim().get_hl(&get_icon(&self.0)?.1)?.reverse_fg_bg())
+    }
+
+    fn visible(&self, win: &WinContext) -> bool {
+        win.width >= ALT_FILE_NAME_MIN_WIDTH
     }
 }
 
@@ -225,6 +254,19 @@ impl Tile for FileStatus {
         HighlightOpt::default()
     }
 
+    fn events(&self) -> Option<&'static [&'static str]> {
+        Some(&[
+            "BufEnter",
+            "BufModifiedSet",
+            "OptionSet modifiable",
+            "OptionSet readonly",
+        ])
+    }
+
+    fn priority(&self) -> u8 {
+        80
+    }
+
     fn update_highlight(&self, old_opt: HighlightOpt) -> nvim_oxi::Result<HighlightOpt> {
         Ok(match self.0 {
             FileStatusFlag::Modified => HighlightOpt::with_bg(Green),
@@ -276,24 +318,22 @@ impl Tile for Lsp {
     }
 
     fn content(&self) -> nvim_oxi::Result<String> {
-        Ok(vim()?
-            .get::<Table>("lsp")?
-            .call_function::<Table>(
-                "get_clients",
-                table! {
-                    bufnr = Buffer::current().handle()
-                },
-            )?
-            .get::<Table>(1)
-            .map(|_| icons::GOOD)
-            .unwrap_or(icons::BAD)
-            .into())
+        Ok(if buffer_clients(0, None)?.is_empty() {
+            glyph(icons::BAD, "off")
+        } else {
+            glyph(icons::GOOD, "on")
+        }
+        .into())
     }
 
     fn highlight_name(&self) -> nvim_oxi::Result<String> {
         Ok(format!("StatusLsp{}", get_icon(&self.0)?.1))
     }
 
+    fn events(&self) -> Option<&'static [&'static str]> {
+        Some(&["BufEnter", "FileType", "LspAttach", "LspDetach"])
+    }
+
     fn highlight_opt(&self) -> HighlightOpt {
         HighlightOpt::with_bg(Blue)
     }
@@ -304,7 +344,7 @@ impl Tile for Lsp {
     }
 
     fn update_highlight(&self, _old_opt: HighlightOpt) -> nvim_oxi::Result<HighlightOpt> {
-        Ok(get_hl(get_icon(&self.0)?.1)?.reverse_fg_bg())
+        Ok(nvim().get_hl(&get_icon(&self.0)?.1)?.reverse_fg_bg())
     }
 }
 
@@ -342,8 +382,8 @@ impl Tile for Formatter {
         Ok(require("conform")?
             .call_function::<Table>("list_formatters", ())?
             .get::<Table>(1)
-            .map(|_| icons::GOOD)
-            .unwrap_or(icons::BAD)
+            .map(|_| glyph(icons::GOOD, "on"))
+            .unwrap_or(glyph(icons::BAD, "off"))
             .into())
     }
 
@@ -351,6 +391,10 @@ impl Tile for Formatter {
         Ok(format!("StatusFormatter{}", get_icon(&self.0)?.1))
     }
 
+    fn events(&self) -> Option<&'static [&'static str]> {
+        Some(&["BufEnter", "FileType", "LspAttach", "LspDetach"])
+    }
+
     fn highlight_opt(&self) -> HighlightOpt {
         HighlightOpt::with_bg(Blue)
     }
@@ -361,7 +405,7 @@ impl Tile for Formatter {
     }
 
     fn update_highlight(&self, _old_opt: HighlightOpt) -> nvim_oxi::Result<HighlightOpt> {
-        Ok(get_hl(get_icon(&self.0)?.1)?.reverse_fg_bg())
+        Ok(nvim().get_hl(&get_icon(&self.0)?.1)?.reverse_fg_bg())
     }
 }
 
@@ -369,7 +413,8 @@ impl Tile for Formatter {
 ///
 /// The tile will display a gear icon if only a lsp is found, a paint brush if only a formatter is
 /// found, if both are found a tick mark will be display and if neither are found a cross is shown.
-/// The Tile also change color based on the file type using `nvim-web-devicons`.
+/// The Tile also change color based on the file type using `nvim-web-devicons`. Clicking the tile
+/// open `:CrabbyLspInfo`. The tile is only shown for normal file buffers.
 pub struct Tools(String);
 
 impl Tools {
@@ -393,22 +438,13 @@ impl Tile for Tools {
             .len()?
             > 0;
 
-        let lsp_attach = vim()?
-            .get::<Table>("lsp")?
-            .call_function::<Table>(
-                "get_clients",
-                table! {
-                    bufnr = Buffer::current().handle()
-                },
-            )?
-            .len()?
-            > 0;
+        let lsp_attach = !buffer_clients(0, None)?.is_empty();
 
         Ok(match (formatter_attach, lsp_attach) {
-            (true, true) => icons::GOOD,
-            (true, false) => icons::FORMATTER,
-            (false, true) => icons::LSP,
-            (false, false) => icons::BAD,
+            (true, true) => glyph(icons::GOOD, "lsp+fmt"),
+            (true, false) => glyph(icons::FORMATTER, "fmt"),
+            (false, true) => glyph(icons::LSP, "lsp"),
+            (false, false) => glyph(icons::BAD, "-"),
         }
         .into())
     }
@@ -417,6 +453,14 @@ impl Tile for Tools {
         Ok(format!("StatusTools{}", get_icon(&self.0)?.1))
     }
 
+    fn events(&self) -> Option<&'static [&'static str]> {
+        Some(&["BufEnter", "FileType", "LspAttach", "LspDetach"])
+    }
+
+    fn priority(&self) -> u8 {
+        60
+    }
+
     fn highlight_opt(&self) -> HighlightOpt {
         HighlightOpt::with_bg(Blue)
     }
@@ -427,6 +471,377 @@ impl Tile for Tools {
     }
 
     fn update_highlight(&self, _old_opt: HighlightOpt) -> nvim_oxi::Result<HighlightOpt> {
-        Ok(get_hl(get_icon(&self.0)?.1)?.bg(STATUS_LINE_FG))
+        Ok(nvim().get_hl(&get_icon(&self.0)?.1)?.bg(STATUS_LINE_FG))
+    }
+
+    fn clickable(&self) -> bool {
+        true
+    }
+
+    fn on_click(&mut self, _clicks: i64, _button: &str, _modifiers: &str) -> nvim_oxi::Result<()> {
+        nvim_oxi::api::command("CrabbyLspInfo")?;
+        Ok(())
+    }
+
+    fn visible(&self, win: &WinContext) -> bool {
+        win.buftype.is_empty()
+    }
+}
+
+/// Tile to show when LSP inlay hints are enabled for the current buffer.
+///
+/// The tile is hidden when inlay hints are disabled. See `:CrabbyInlayHints` to toggle them.
+pub struct InlayHints(bool);
+
+impl InlayHints {
+    #[must_use]
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self(false)
+    }
+}
+
+impl Default for InlayHints {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tile for InlayHints {
+    fn content(&self) -> nvim_oxi::Result<String> {
+        Ok(if self.0 {
+            glyph(icons::INLAY_HINT, "hints").into()
+        } else {
+            String::new()
+        })
+    }
+
+    fn highlight_name(&self) -> nvim_oxi::Result<String> {
+        Ok("StatusInlayHints".into())
+    }
+
+    fn events(&self) -> Option<&'static [&'static str]> {
+        Some(&["BufEnter", "LspAttach", "User CrabbyInlayHints"])
+    }
+
+    fn priority(&self) -> u8 {
+        20
+    }
+
+    fn highlight_opt(&self) -> HighlightOpt {
+        HighlightOpt::with_bg(Purple)
+    }
+
+    fn update(&mut self) -> nvim_oxi::Result<()> {
+        self.0 = inlay_hints_enabled(&Buffer::current())?;
+        Ok(())
+    }
+}
+
+/// Lines scanned from the top of the buffer to detect its indentation.
+const INDENT_SCAN_LINES: i64 = 500;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(super) enum Indent {
+    Tabs,
+    Spaces(usize),
+}
+
+impl std::fmt::Display for Indent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Indent::Tabs => write!(f, "tabs"),
+            Indent::Spaces(width) => write!(f, "{width} spaces"),
+        }
+    }
+}
+
+/// Guess the indentation of `lines`, the width of space indentation is the most common increase of
+/// indentation between two lines. `None` if no line is indented.
+pub(super) fn detect_indent(lines: &[String]) -> Option<Indent> {
+    let (mut tabs, mut spaces) = (0, 0);
+    let mut increases = HashMap::<usize, usize>::new();
+    let mut previous = 0;
+
+    for line in lines {
+        let trimmed = line.trim_start();
+        // skip the continuation of block comments like ` * text`
+        if trimmed.is_empty() || trimmed.starts_with('*') {
+            continue;
+        }
+        if line.starts_with('\t') {
+            tabs += 1;
+            continue;
+        }
+
+        let width = line.len() - line.trim_start_matches(' ').len();
+        if width > 0 {
+            spaces += 1;
+        }
+        if width > previous {
+            *increases.entry(width - previous).or_default() += 1;
+        }
+        previous = width;
+    }
+
+    if tabs == 0 && spaces == 0 {
+        None
+    } else if tabs > spaces {
+        Some(Indent::Tabs)
+    } else {
+        increases
+            .into_iter()
+            .max_by_key(|&(width, count)| (count, std::cmp::Reverse(width)))
+            .map(|(width, _)| Indent::Spaces(width))
+    }
+}
+
+/// Tile to show the line endings, encoding and indentation of the current file when they differ
+/// from the global `fileformats`, `encoding`, `expandtab` and `shiftwidth` options.
+///
+/// The tile is hidden when the file match every default.
+pub struct FileFormat(Vec<String>);
+
+impl FileFormat {
+    #[must_use]
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self(vec![])
+    }
+}
+
+impl Default for FileFormat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tile for FileFormat {
+    fn content(&self) -> nvim_oxi::Result<String> {
+        Ok(self.0.join(" "))
+    }
+
+    fn highlight_name(&self) -> nvim_oxi::Result<String> {
+        Ok("StatusFileFormat".into())
+    }
+
+    fn events(&self) -> Option<&'static [&'static str]> {
+        Some(&[
+            "BufEnter",
+            "BufWritePost",
+            "FileType",
+            "OptionSet fileformat",
+            "OptionSet fileencoding",
+            "OptionSet expandtab",
+            "OptionSet shiftwidth",
+            "OptionSet tabstop",
+        ])
+    }
+
+    fn priority(&self) -> u8 {
+        30
+    }
+
+    fn highlight_opt(&self) -> HighlightOpt {
+        HighlightOpt::with_bg(Yellow)
+    }
+
+    fn update(&mut self) -> nvim_oxi::Result<()> {
+        let go = vim()?.get::<Table>("go")?;
+        self.0.clear();
+
+        let format = get_option::<String>("fileformat")?;
+        if go.get::<String>("fileformats")?.split(',').next() != Some(format.as_str()) {
+            self.0.push(
+                match format.as_str() {
+                    "dos" => "CRLF",
+                    "mac" => "CR",
+                    _ => "LF",
+                }
+                .into(),
+            );
+        }
+
+        let encoding = get_option::<String>("fileencoding")?;
+        if !encoding.is_empty() && encoding != go.get::<String>("encoding")? {
+            self.0.push(encoding);
+        }
+
+        let default = if go.get::<bool>("expandtab")? {
+            // a `shiftwidth` of 0 use the value of `tabstop`
+            match go.get::<usize>("shiftwidth")? {
+                0 => Indent::Spaces(go.get::<usize>("tabstop")?),
+                width => Indent::Spaces(width),
+            }
+        } else {
+            Indent::Tabs
+        };
+        let lines = vim()?
+            .get::<Table>("api")?
+            .call_function::<Vec<String>>("nvim_buf_get_lines", (0, 0, INDENT_SCAN_LINES, false))?;
+        if let Some(indent) = detect_indent(&lines)
+            && indent != default
+        {
+            self.0.push(indent.to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// File types where [`WordCount`] is shown.
+const PROSE_FILETYPES: &[&str] = &["markdown", "typst", "text"];
+
+/// Reading speed used by [`WordCount`] to estimate the reading time.
+const WORDS_PER_MINUTE: usize = 200;
+
+/// Typst statements whose lines have no prose.
+const TYPST_STATEMENTS: [&str; 5] = ["#set ", "#let ", "#show ", "#import ", "#include "];
+
+/// Count the words of `lines`, skipping code blocks as well as the code and markup of Typst when
+/// `typst` is set. Only the content of the Typst function calls like `#emph[word]` is counted.
+pub(super) fn count_words(lines: &[String], typst: bool) -> usize {
+    let mut in_code = false;
+    let mut count = 0;
+
+    for line in lines {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || (!typst && trimmed.starts_with("~~~")) {
+            in_code = !in_code;
+            continue;
+        }
+        // statements and comments
+        if in_code
+            || (typst
+                && (TYPST_STATEMENTS
+                    .iter()
+                    .any(|statement| trimmed.starts_with(statement))
+                    || trimmed.starts_with("//")))
+        {
+            continue;
+        }
+
+        count += trimmed
+            .split_whitespace()
+            .filter_map(|word| match word.strip_prefix('#') {
+                // the content of a function call, nothing for `#v(1em)`
+                Some(call) if typst => call.split_once('[').map(|(_, content)| content),
+                _ => Some(word),
+            })
+            .filter(|word| {
+                // labels and references of typst
+                let markup = typst
+                    && ((word.starts_with('<') && word.ends_with('>')) || word.starts_with('@'));
+                // lone markup like `=`, `-` or `*` isn't a word
+                !markup && word.chars().any(char::is_alphanumeric)
+            })
+            .count();
+    }
+
+    count
+}
+
+/// Tile to show the word count and the estimated reading time of prose files like markdown and
+/// Typst, or the word count of the selection in visual mode.
+///
+/// The count of the buffer is only recomputed when its `changedtick` change.
+pub struct WordCount {
+    /// The buffer and `changedtick` the count was computed for.
+    counted: Option<(i64, i64)>,
+    words: usize,
+    selection: Option<usize>,
+    prose: bool,
+}
+
+impl WordCount {
+    #[must_use]
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {
+            counted: None,
+            words: 0,
+            selection: None,
+            prose: false,
+        }
+    }
+}
+
+impl Default for WordCount {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tile for WordCount {
+    fn content(&self) -> nvim_oxi::Result<String> {
+        if !self.prose {
+            return Ok(String::new());
+        }
+
+        Ok(match self.selection {
+            Some(selection) => format!("{selection}/{} words", self.words),
+            None => format!(
+                "{} words {} min",
+                self.words,
+                self.words.div_ceil(WORDS_PER_MINUTE)
+            ),
+        })
+    }
+
+    fn highlight_name(&self) -> nvim_oxi::Result<String> {
+        Ok("StatusWordCount".into())
+    }
+
+    fn priority(&self) -> u8 {
+        25
+    }
+
+    fn compact_content(&self) -> nvim_oxi::Result<Option<String>> {
+        Ok(self
+            .prose
+            .then(|| format!("{}w", self.selection.unwrap_or(self.words))))
+    }
+
+    fn highlight_opt(&self) -> HighlightOpt {
+        HighlightOpt::with_bg(Green)
+    }
+
+    fn update(&mut self) -> nvim_oxi::Result<()> {
+        let filetype = get_option::<String>("filetype")?;
+        self.prose = PROSE_FILETYPES.contains(&filetype.as_str());
+        if !self.prose {
+            return Ok(());
+        }
+
+        let typst = filetype == "typst";
+        let api = vim()?.get::<Table>("api")?;
+
+        let counted = (
+            api.call_function::<i64>("nvim_get_current_buf", ())?,
+            vim()?.get::<Table>("b")?.get::<i64>("changedtick")?,
+        );
+        if self.counted != Some(counted) {
+            let lines =
+                api.call_function::<Vec<String>>("nvim_buf_get_lines", (0, 0, -1, false))?;
+            self.words = count_words(&lines, typst);
+            self.counted = Some(counted);
+        }
+
+        let mode = vim_fn::<String>("mode", ())?;
+        self.selection = if matches!(mode.as_str(), "v" | "V" | "\u{16}") {
+            let lines = vim_fn::<Vec<String>>(
+                "getregion",
+                (
+                    vim_fn::<Table>("getpos", "v")?,
+                    vim_fn::<Table>("getpos", ".")?,
+                    table! { ["type"] = mode },
+                ),
+            )?;
+            Some(count_words(&lines, typst))
+        } else {
+            None
+        };
+
+        Ok(())
     }
 }
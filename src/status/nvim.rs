@@ -0,0 +1,100 @@
+use std::{cell::RefCell, rc::Rc};
+
+use nvim_oxi::api::types::LogLevel;
+
+use crate::{diagnostic::DiagnosticSeverity, theme::HighlightOpt};
+
+use super::WinContext;
+
+// the tests replace it with a fake so they don't need the Neovim API
+#[cfg(not(test))]
+mod neovim;
+#[cfg(not(test))]
+pub use neovim::Neovim;
+
+/// The Neovim calls made while rendering a [`Line`](super::Line) and its common tiles.
+///
+/// Every call goes through [`nvim`] so the tests can swap [`Neovim`] for a fake with
+/// [`set_nvim`] and render lines without a running Neovim.
+pub trait Nvim {
+    /// The current window.
+    fn current_win(&self) -> nvim_oxi::Result<i64>;
+
+    /// The window the line is drawn for, see [`status_window`](super::status_window).
+    fn status_window(&self) -> nvim_oxi::Result<i64>;
+
+    /// The width of `win`.
+    fn win_width(&self, win: i64) -> nvim_oxi::Result<usize>;
+
+    /// The width of the whole editor.
+    fn columns(&self) -> nvim_oxi::Result<usize>;
+
+    /// The current window and its buffer, with the width of the window.
+    fn win_context(&self) -> nvim_oxi::Result<WinContext>;
+
+    /// The display width of a statusline string.
+    fn status_width(&self, str: &str) -> nvim_oxi::Result<usize>;
+
+    /// Set the highlight group `name`.
+    fn set_hl(&self, name: &str, opt: HighlightOpt) -> nvim_oxi::Result<()>;
+
+    /// The highlight group `name`.
+    fn get_hl(&self, name: &str) -> nvim_oxi::Result<HighlightOpt>;
+
+    /// Show `msg` to the user.
+    fn notify(&self, msg: &str, level: LogLevel) -> nvim_oxi::Result<()>;
+
+    /// Call `callback` whenever one of `events` fire, `User` events are written with their
+    /// pattern like `User GitSignsUpdate`.
+    fn on_events(&self, events: &[&str], callback: Rc<dyn Fn()>) -> nvim_oxi::Result<()>;
+
+    /// Call `callback` with the window being closed whenever a window is closed.
+    fn on_win_closed(&self, callback: Rc<dyn Fn(i64)>) -> nvim_oxi::Result<()>;
+
+    /// Call `f` in the context of `win` and return its result.
+    fn win_call(
+        &self,
+        win: i64,
+        f: &mut dyn FnMut() -> nvim_oxi::Result<String>,
+    ) -> nvim_oxi::Result<String>;
+
+    /// The current mode.
+    fn mode(&self) -> nvim_oxi::Result<crate::Mode>;
+
+    /// The amount of diagnostics of `severity` in the current buffer or in every buffer when
+    /// `global` is `true`.
+    fn diagnostic_count(&self, severity: DiagnosticSeverity, global: bool)
+    -> nvim_oxi::Result<i64>;
+
+    /// The current branch given by `gitsigns.nvim`, `None` outside of a repository.
+    fn git_head(&self) -> Option<String>;
+
+    /// The amount of added, changed and removed lines of the current buffer given by
+    /// `gitsigns.nvim`.
+    fn git_diff(&self) -> (i64, i64, i64);
+}
+
+thread_local! {
+    static NVIM: RefCell<Option<Rc<dyn Nvim>>> = const { RefCell::new(None) };
+}
+
+/// The [`Nvim`] used by the lines and tiles, [`Neovim`] unless replaced with [`set_nvim`].
+#[must_use]
+pub fn nvim() -> Rc<dyn Nvim> {
+    NVIM.with(|nvim| nvim.borrow_mut().get_or_insert_with(default_nvim).clone())
+}
+
+/// Replace the [`Nvim`] used by the lines and tiles, like with a fake in the tests.
+pub fn set_nvim(nvim: impl Nvim + 'static) {
+    NVIM.with(|cell| *cell.borrow_mut() = Some(Rc::new(nvim)));
+}
+
+#[cfg(not(test))]
+fn default_nvim() -> Rc<dyn Nvim> {
+    Rc::new(Neovim)
+}
+
+#[cfg(test)]
+fn default_nvim() -> Rc<dyn Nvim> {
+    panic!("the tests must install a fake `Nvim` with `set_nvim`")
+}
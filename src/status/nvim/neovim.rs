@@ -0,0 +1,181 @@
+use std::rc::Rc;
+
+use mlua::{ObjectLike, Table};
+use nvim_oxi::{
+    Dictionary,
+    api::{Buffer, types::LogLevel},
+};
+
+use crate::{
+    autocmds::create_autocmd,
+    diagnostic::DiagnosticSeverity,
+    options::get_option,
+    status::{Nvim, WinContext, eval_status},
+    table,
+    theme::HighlightOpt,
+    vim, vim_notify,
+};
+
+/// The [`Nvim`] implementation calling the actual Neovim API.
+pub struct Neovim;
+
+impl Nvim for Neovim {
+    fn current_win(&self) -> nvim_oxi::Result<i64> {
+        Ok(vim()?
+            .get::<Table>("api")?
+            .call_function::<i64>("nvim_get_current_win", ())?)
+    }
+
+    fn status_window(&self) -> nvim_oxi::Result<i64> {
+        match nvim_oxi::api::get_var::<i64>("statusline_winid") {
+            Ok(win)
+                if vim()?
+                    .get::<Table>("api")?
+                    .call_function::<bool>("nvim_win_is_valid", win)? =>
+            {
+                Ok(win)
+            }
+            _ => self.current_win(),
+        }
+    }
+
+    fn win_width(&self, win: i64) -> nvim_oxi::Result<usize> {
+        let width = vim()?
+            .get::<Table>("api")?
+            .call_function::<i64>("nvim_win_get_width", win)?;
+        Ok(usize::try_from(width).unwrap_or_default())
+    }
+
+    fn columns(&self) -> nvim_oxi::Result<usize> {
+        Ok(usize::try_from(get_option::<i64>("columns")?).unwrap_or_default())
+    }
+
+    fn win_context(&self) -> nvim_oxi::Result<WinContext> {
+        let win = self.current_win()?;
+        let relative = vim()?
+            .get::<Table>("api")?
+            .call_function::<Table>("nvim_win_get_config", win)?
+            .get::<String>("relative")?;
+
+        Ok(WinContext {
+            buftype: get_option::<String>("buftype")?,
+            filetype: get_option::<String>("filetype")?,
+            width: self.win_width(win)?,
+            float: !relative.is_empty(),
+            diff: get_option::<bool>("diff")?,
+        })
+    }
+
+    fn status_width(&self, str: &str) -> nvim_oxi::Result<usize> {
+        // Janky fix for nvim_oxi bug:
+        // https://github.com/noib3/nvim-oxi/issues/267
+        // TODO: update nvim_oxi when this bug is fix
+        if str.is_empty() {
+            return Ok(0);
+        }
+        Ok(usize::try_from(eval_status(str)?.width).unwrap_or_default())
+    }
+
+    fn set_hl(&self, name: &str, opt: HighlightOpt) -> nvim_oxi::Result<()> {
+        crate::theme::set_hl(name, opt)
+    }
+
+    fn get_hl(&self, name: &str) -> nvim_oxi::Result<HighlightOpt> {
+        crate::theme::get_hl(name)
+    }
+
+    fn notify(&self, msg: &str, level: LogLevel) -> nvim_oxi::Result<()> {
+        vim_notify(msg, level)
+    }
+
+    fn on_events(&self, events: &[&str], callback: Rc<dyn Fn()>) -> nvim_oxi::Result<()> {
+        for event in events {
+            let (event, pattern) = event.split_once(' ').unwrap_or((event, "*"));
+            let callback = callback.clone();
+
+            nvim_oxi::api::create_autocmd(
+                [event],
+                &nvim_oxi::api::opts::CreateAutocmdOpts::builder()
+                    .patterns([pattern])
+                    .callback(move |_| -> nvim_oxi::Result<bool> {
+                        callback();
+                        Ok(false)
+                    })
+                    .build(),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn on_win_closed(&self, callback: Rc<dyn Fn(i64)>) -> nvim_oxi::Result<()> {
+        create_autocmd(&["WinClosed"], &["*"], move |args| {
+            if let Ok(win) = args.r#match.parse::<i64>() {
+                callback(win);
+            }
+            Ok(())
+        })
+    }
+
+    fn win_call(
+        &self,
+        win: i64,
+        f: &mut dyn FnMut() -> nvim_oxi::Result<String>,
+    ) -> nvim_oxi::Result<String> {
+        Ok(nvim_oxi::mlua::lua().scope(|scope| {
+            vim()
+                .map_err(mlua::Error::external)?
+                .get::<Table>("api")?
+                .call_function::<String>(
+                    "nvim_win_call",
+                    (
+                        win,
+                        scope.create_function_mut(|_, ()| f().map_err(mlua::Error::external))?,
+                    ),
+                )
+        })?)
+    }
+
+    fn mode(&self) -> nvim_oxi::Result<crate::Mode> {
+        crate::Mode::current_mode()
+    }
+
+    fn diagnostic_count(
+        &self,
+        severity: DiagnosticSeverity,
+        global: bool,
+    ) -> nvim_oxi::Result<i64> {
+        Ok(vim()?
+            .get::<Table>("diagnostic")?
+            .call_function::<Table>(
+                "get",
+                (
+                    if global {
+                        mlua::Nil
+                    } else {
+                        mlua::Value::Integer(0)
+                    },
+                    table! {severity = severity},
+                ),
+            )?
+            .len()?)
+    }
+
+    fn git_head(&self) -> Option<String> {
+        nvim_oxi::api::get_var::<String>("gitsigns_head").ok()
+    }
+
+    fn git_diff(&self) -> (i64, i64, i64) {
+        match Buffer::current().get_var::<Dictionary>("gitsigns_status_dict") {
+            Ok(dict) => (
+                dict.get("added")
+                    .map_or(0, |o| unsafe { o.as_integer_unchecked() }),
+                dict.get("changed")
+                    .map_or(0, |o| unsafe { o.as_integer_unchecked() }),
+                dict.get("removed")
+                    .map_or(0, |o| unsafe { o.as_integer_unchecked() }),
+            ),
+            Err(_) => (0, 0, 0),
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use mlua::{ObjectLike, Table};
+
+use crate::{
+    icons,
+    plugins::devicons::get_icon,
+    table,
+    theme::{Color::*, HighlightOpt},
+    vim, vim_fn,
+};
+
+use super::{STATUS_LINE_FG, Tile, TileStyle, glyph, nvim};
+
+struct BufferItem {
+    rendered: String,
+    width: usize,
+    current: bool,
+}
+
+/// Tile to show every listed buffer with its icon, a modified marker and its error and warning
+/// count.
+///
+/// When the buffers don't fit, only the buffers around the current buffer are shown along with the
+/// amount of hidden buffers on each side. The buffers take the width left by the other tiles of
+/// the line. Clicking on a buffer switch to it.
+pub struct BufferList {
+    items: Vec<BufferItem>,
+    /// The width left by the other tiles, see [`Tile::set_width`].
+    width: usize,
+}
+
+impl BufferList {
+    #[must_use]
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {
+            items: vec![],
+            width: 0,
+        }
+    }
+
+    fn render_buffer(info: &Table, current: i64) -> nvim_oxi::Result<BufferItem> {
+        let buf = info.get::<i64>("bufnr")?;
+        let name = info.get::<String>("name")?;
+        let file = if name.is_empty() {
+            "[No Name]".to_string()
+        } else {
+            vim_fn::<String>("fnamemodify", (name, ":t"))?
+        };
+
+        let counts = vim()?
+            .get::<Table>("diagnostic")?
+            .call_function::<Table>("count", buf)?;
+        let (error, warn) = (
+            counts.get::<Option<i64>>(1)?.unwrap_or(0),
+            counts.get::<Option<i64>>(2)?.unwrap_or(0),
+        );
+
+        let base = if buf == current {
+            "TabBufferCurrent"
+        } else {
+            "TabBuffer"
+        };
+        let label = match glyph(&get_icon(&file)?.0, "") {
+            "" => file,
+            icon => format!("{icon} {file}"),
+        };
+        let mut parts = vec![(label, base)];
+
+        if info.get::<i64>("changed")? == 1 {
+            parts.push((
+                format!(" {}", glyph(icons::MODIFIED, "+")),
+                "TabBufferModified",
+            ));
+        }
+        if error > 0 {
+            parts.push((
+                format!(" {} {error}", glyph(icons::ERROR, "E")),
+                "TabBufferError",
+            ));
+        }
+        if warn > 0 {
+            parts.push((
+                format!(" {} {warn}", glyph(icons::WARN, "W")),
+                "TabBufferWarn",
+            ));
+        }
+
+        let plain = parts
+            .iter()
+            .map(|(text, _)| text.as_str())
+            .collect::<String>();
+        let highlighted = parts
+            .iter()
+            .map(|(text, hl)| format!("%#{hl}#{}", text.replace('%', "%%")))
+            .collect::<String>();
+
+        Ok(BufferItem {
+            rendered: format!("%{buf}@v:lua.crabbyvim_buffer_click@{highlighted}%X"),
+            width: vim_fn::<usize>("strdisplaywidth", plain)?,
+            current: buf == current,
+        })
+    }
+}
+
+impl Default for BufferList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tile for BufferList {
+    fn content(&self) -> nvim_oxi::Result<String> {
+        let items = &self.items;
+        if items.is_empty() {
+            return Ok(String::new());
+        }
+
+        // leave room for the separators and the overflow indicators
+        let budget = self.width.saturating_sub(8);
+        let current = items.iter().position(|item| item.current).unwrap_or(0);
+
+        // grow the visible range around the current buffer until it doesn't fit anymore
+        let (mut start, mut end, mut width) = (current, current + 1, items[current].width);
+        loop {
+            let mut grew = false;
+            if end < items.len() && width + 1 + items[end].width <= budget {
+                width += 1 + items[end].width;
+                end += 1;
+                grew = true;
+            }
+            if start > 0 && width + 1 + items[start - 1].width <= budget {
+                width += 1 + items[start - 1].width;
+                start -= 1;
+                grew = true;
+            }
+            if !grew {
+                break;
+            }
+        }
+
+        let mut out = vec![];
+        if start > 0 {
+            out.push(format!(
+                "%#TabBuffer#{} {start}",
+                glyph(icons::OVERFLOW_LEFT, "<")
+            ));
+        }
+        out.extend(items[start..end].iter().map(|item| item.rendered.clone()));
+        if end < items.len() {
+            out.push(format!(
+                "%#TabBuffer#{} {}",
+                items.len() - end,
+                glyph(icons::OVERFLOW_RIGHT, ">")
+            ));
+        }
+
+        Ok(out.join("%#TabBuffer# "))
+    }
+
+    fn highlight_name(&self) -> nvim_oxi::Result<String> {
+        Ok("StatusBufferList".into())
+    }
+
+    fn events(&self) -> Option<&'static [&'static str]> {
+        Some(&[
+            "BufAdd",
+            "BufDelete",
+            "BufEnter",
+            "BufFilePost",
+            "BufModifiedSet",
+            "DiagnosticChanged",
+            "OptionSet buflisted",
+            "VimResized",
+        ])
+    }
+
+    fn fills_line(&self) -> bool {
+        true
+    }
+
+    fn set_width(&mut self, width: usize) -> bool {
+        width != std::mem::replace(&mut self.width, width)
+    }
+
+    fn highlight_opt(&self) -> HighlightOpt {
+        HighlightOpt::with_bg(STATUS_LINE_FG)
+    }
+
+    fn priority(&self) -> u8 {
+        100
+    }
+
+    fn setup(&self) -> nvim_oxi::Result<()> {
+        set_tab_highlights()?;
+
+        let lua = nvim_oxi::mlua::lua();
+        lua.globals().set(
+            "crabbyvim_buffer_click",
+            lua.create_function(|_, (buf, _, button): (i64, i64, String)| {
+                if button == "l" {
+                    vim()?
+                        .get::<Table>("api")?
+                        .call_function::<()>("nvim_set_current_buf", buf)?;
+                }
+                Ok(())
+            })?,
+        )?;
+
+        Ok(())
+    }
+
+    fn update(&mut self) -> nvim_oxi::Result<()> {
+        let current = vim()?
+            .get::<Table>("api")?
+            .call_function::<i64>("nvim_get_current_buf", ())?;
+
+        self.items = vim_fn::<Vec<Table>>("getbufinfo", table! { buflisted = 1 })?
+            .iter()
+            .map(|info| Self::render_buffer(info, current))
+            .collect::<nvim_oxi::Result<_>>()?;
+
+        Ok(())
+    }
+}
+
+/// Tile to show the tab pages, only shown when there is more than one tab. Clicking on a tab switch
+/// to it.
+pub struct TabList {
+    count: usize,
+    current: usize,
+}
+
+impl TabList {
+    #[must_use]
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {
+            count: 0,
+            current: 0,
+        }
+    }
+}
+
+impl Default for TabList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tile for TabList {
+    fn style(&self) -> TileStyle {
+        TileStyle::Icon
+    }
+
+    fn icon(&self) -> nvim_oxi::Result<String> {
+        Ok(icons::TAB.into())
+    }
+
+    fn content(&self) -> nvim_oxi::Result<String> {
+        if self.count <= 1 {
+            return Ok(String::new());
+        }
+
+        Ok((1..=self.count)
+            .map(|tab| {
+                let hl = if tab == self.current {
+                    "TabBufferCurrent"
+                } else {
+                    "TabBuffer"
+                };
+                format!("%{tab}T%#{hl}#{tab}")
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+            + "%T")
+    }
+
+    fn highlight_name(&self) -> nvim_oxi::Result<String> {
+        Ok("StatusTabList".into())
+    }
+
+    fn events(&self) -> Option<&'static [&'static str]> {
+        Some(&["TabNew", "TabClosed", "TabEnter"])
+    }
+
+    fn highlight_opt(&self) -> HighlightOpt {
+        HighlightOpt::with_bg(Purple)
+    }
+
+    fn priority(&self) -> u8 {
+        90
+    }
+
+    fn setup(&self) -> nvim_oxi::Result<()> {
+        set_tab_highlights()
+    }
+
+    fn update(&mut self) -> nvim_oxi::Result<()> {
+        self.count = vim_fn::<usize>("tabpagenr", "$")?;
+        self.current = vim_fn::<usize>("tabpagenr", ())?;
+        Ok(())
+    }
+}
+
+/// The highlight groups of the items in [`BufferList`] and [`TabList`].
+fn set_tab_highlights() -> nvim_oxi::Result<()> {
+    let nvim = nvim();
+    nvim.set_hl("TabBuffer", HighlightOpt::with_fg(Gray).bg(STATUS_LINE_FG))?;
+    nvim.set_hl(
+        "TabBufferCurrent",
+        HighlightOpt::with_fg(White).bg(STATUS_LINE_FG).bold(),
+    )?;
+    nvim.set_hl(
+        "TabBufferModified",
+        HighlightOpt::with_fg(Green).bg(STATUS_LINE_FG),
+    )?;
+    nvim.set_hl(
+        "TabBufferError",
+        HighlightOpt::with_fg(Red).bg(STATUS_LINE_FG),
+    )?;
+    nvim.set_hl(
+        "TabBufferWarn",
+        HighlightOpt::with_fg(Yellow).bg(STATUS_LINE_FG),
+    )?;
+    Ok(())
+}
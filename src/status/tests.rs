@@ -0,0 +1,748 @@
+use std::cell::Cell;
+
+use super::local_tiles::{Indent, count_words, detect_indent};
+use super::*;
+use crate::diagnostic::DiagnosticSeverity;
+
+/// A [`Nvim`] returning fixed values, `highlights`, `events` and `notifications` record the calls
+/// made by the line.
+struct FakeNvim {
+    mode: Cell<crate::Mode>,
+    width: usize,
+    buftype: &'static str,
+    filetype: &'static str,
+    float: bool,
+    /// Error, warning, hint and info count.
+    diagnostics: [i64; 4],
+    git_head: Option<&'static str>,
+    git_diff: (i64, i64, i64),
+    highlights: RefCell<HashMap<String, HighlightOpt>>,
+    events: RefCell<Vec<(String, Rc<dyn Fn()>)>>,
+    closed: RefCell<Vec<Rc<dyn Fn(i64)>>>,
+    notifications: RefCell<Vec<String>>,
+}
+
+impl Default for FakeNvim {
+    fn default() -> Self {
+        Self {
+            mode: Cell::new(crate::Mode::Normal),
+            width: 80,
+            buftype: "",
+            filetype: "rust",
+            float: false,
+            diagnostics: [0; 4],
+            git_head: None,
+            git_diff: (0, 0, 0),
+            highlights: RefCell::default(),
+            events: RefCell::default(),
+            closed: RefCell::default(),
+            notifications: RefCell::default(),
+        }
+    }
+}
+
+impl FakeNvim {
+    /// Install this fake as the [`Nvim`] of the current thread.
+    fn install(self) -> Rc<Self> {
+        let fake = Rc::new(self);
+        set_nvim(fake.clone());
+        fake
+    }
+
+    /// Call the callbacks registered for `event`.
+    fn fire(&self, event: &str) {
+        let callbacks = self
+            .events
+            .borrow()
+            .iter()
+            .filter(|(name, _)| name == event)
+            .map(|(_, callback)| callback.clone())
+            .collect::<Vec<_>>();
+        callbacks.iter().for_each(|callback| callback());
+    }
+
+    /// Call the callbacks registered for the closing of `win`.
+    fn close_win(&self, win: i64) {
+        let callbacks = self.closed.borrow().clone();
+        callbacks.iter().for_each(|callback| callback(win));
+    }
+}
+
+impl Nvim for Rc<FakeNvim> {
+    fn current_win(&self) -> nvim_oxi::Result<i64> {
+        Ok(1000)
+    }
+
+    fn status_window(&self) -> nvim_oxi::Result<i64> {
+        Ok(1000)
+    }
+
+    fn win_width(&self, _win: i64) -> nvim_oxi::Result<usize> {
+        Ok(self.width)
+    }
+
+    fn columns(&self) -> nvim_oxi::Result<usize> {
+        Ok(self.width)
+    }
+
+    fn win_context(&self) -> nvim_oxi::Result<WinContext> {
+        Ok(WinContext {
+            buftype: self.buftype.into(),
+            filetype: self.filetype.into(),
+            width: self.width,
+            float: self.float,
+            diff: false,
+        })
+    }
+
+    /// Count the characters left once the statusline items are removed, every glyph of the
+    /// tiles take a single cell.
+    fn status_width(&self, str: &str) -> nvim_oxi::Result<usize> {
+        let mut width = 0;
+        let mut chars = str.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                width += 1;
+                continue;
+            }
+
+            match chars.next() {
+                Some('%') => width += 1,
+                // `%#Group#`
+                Some('#') => {
+                    chars.by_ref().find(|&c| c == '#');
+                }
+                // `%{id}@function@`, `%{id}T` and `%{id}X`
+                Some(c) if c.is_ascii_digit() => {
+                    if chars.by_ref().find(|c| !c.is_ascii_digit()) == Some('@') {
+                        chars.by_ref().find(|&c| c == '@');
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(width)
+    }
+
+    fn set_hl(&self, name: &str, opt: HighlightOpt) -> nvim_oxi::Result<()> {
+        self.highlights.borrow_mut().insert(name.into(), opt);
+        Ok(())
+    }
+
+    fn get_hl(&self, name: &str) -> nvim_oxi::Result<HighlightOpt> {
+        Ok(self
+            .highlights
+            .borrow()
+            .get(name)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn notify(&self, msg: &str, _level: LogLevel) -> nvim_oxi::Result<()> {
+        self.notifications.borrow_mut().push(msg.into());
+        Ok(())
+    }
+
+    fn on_events(&self, events: &[&str], callback: Rc<dyn Fn()>) -> nvim_oxi::Result<()> {
+        self.events.borrow_mut().extend(
+            events
+                .iter()
+                .map(|event| ((*event).to_string(), callback.clone())),
+        );
+        Ok(())
+    }
+
+    fn on_win_closed(&self, callback: Rc<dyn Fn(i64)>) -> nvim_oxi::Result<()> {
+        self.closed.borrow_mut().push(callback);
+        Ok(())
+    }
+
+    fn win_call(
+        &self,
+        _win: i64,
+        f: &mut dyn FnMut() -> nvim_oxi::Result<String>,
+    ) -> nvim_oxi::Result<String> {
+        f()
+    }
+
+    fn mode(&self) -> nvim_oxi::Result<crate::Mode> {
+        Ok(self.mode.get())
+    }
+
+    fn diagnostic_count(
+        &self,
+        severity: DiagnosticSeverity,
+        _global: bool,
+    ) -> nvim_oxi::Result<i64> {
+        Ok(self.diagnostics[match severity {
+            DiagnosticSeverity::Error => 0,
+            DiagnosticSeverity::Warn => 1,
+            DiagnosticSeverity::Hint => 2,
+            DiagnosticSeverity::Info => 3,
+        }])
+    }
+
+    fn git_head(&self) -> Option<String> {
+        self.git_head.map(Into::into)
+    }
+
+    fn git_diff(&self) -> (i64, i64, i64) {
+        self.git_diff
+    }
+}
+
+/// A bubble tile with fixed content highlighted with `Status{name}`.
+struct Text {
+    name: &'static str,
+    content: &'static str,
+    compact: Option<&'static str>,
+    priority: u8,
+}
+
+impl Text {
+    fn new(name: &'static str, content: &'static str) -> Self {
+        Self {
+            name,
+            content,
+            compact: None,
+            priority: 50,
+        }
+    }
+}
+
+impl Tile for Text {
+    fn content(&self) -> nvim_oxi::Result<String> {
+        Ok(self.content.into())
+    }
+
+    fn highlight_name(&self) -> nvim_oxi::Result<String> {
+        Ok(format!("Status{}", self.name))
+    }
+
+    fn highlight_opt(&self) -> HighlightOpt {
+        HighlightOpt::with_bg(Color::Blue)
+    }
+
+    fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    fn compact_content(&self) -> nvim_oxi::Result<Option<String>> {
+        Ok(self.compact.map(Into::into))
+    }
+}
+
+/// A tile filling the width left by the other tiles with `x`, cached until that width change.
+struct Fill(usize);
+
+impl Tile for Fill {
+    fn content(&self) -> nvim_oxi::Result<String> {
+        // the 2 separators of the bubble
+        Ok("x".repeat(self.0.saturating_sub(2)))
+    }
+
+    fn highlight_name(&self) -> nvim_oxi::Result<String> {
+        Ok("StatusFill".into())
+    }
+
+    fn highlight_opt(&self) -> HighlightOpt {
+        HighlightOpt::with_bg(Color::Green)
+    }
+
+    fn events(&self) -> Option<&'static [&'static str]> {
+        Some(&[])
+    }
+
+    fn fills_line(&self) -> bool {
+        true
+    }
+
+    fn set_width(&mut self, width: usize) -> bool {
+        width != std::mem::replace(&mut self.0, width)
+    }
+}
+
+/// A tile that always fail to update.
+struct Failing;
+
+impl Tile for Failing {
+    fn content(&self) -> nvim_oxi::Result<String> {
+        Ok("never shown".into())
+    }
+
+    fn highlight_name(&self) -> nvim_oxi::Result<String> {
+        Ok("StatusFailing".into())
+    }
+
+    fn highlight_opt(&self) -> HighlightOpt {
+        HighlightOpt::with_bg(Color::Red)
+    }
+
+    fn update(&mut self) -> nvim_oxi::Result<()> {
+        Err(mlua::Error::RuntimeError("plugin not loaded".into()).into())
+    }
+}
+
+/// A tile that can't be setup the first time, like when its plugin isn't loaded yet.
+struct LateSetup(Cell<bool>);
+
+impl Tile for LateSetup {
+    fn content(&self) -> nvim_oxi::Result<String> {
+        Ok("late".into())
+    }
+
+    fn highlight_name(&self) -> nvim_oxi::Result<String> {
+        Ok("StatusLate".into())
+    }
+
+    fn highlight_opt(&self) -> HighlightOpt {
+        HighlightOpt::with_bg(Color::Blue)
+    }
+
+    fn setup(&self) -> nvim_oxi::Result<()> {
+        if self.0.replace(true) {
+            Ok(())
+        } else {
+            Err(mlua::Error::RuntimeError("plugin not loaded".into()).into())
+        }
+    }
+}
+
+#[test]
+fn statusline_snapshot() {
+    let fake = FakeNvim {
+        mode: Cell::new(crate::Mode::Insert),
+        diagnostics: [2, 1, 0, 0],
+        git_head: Some("main"),
+        ..Default::default()
+    }
+    .install();
+
+    let mut line = Line::new();
+    line.full_width();
+    line.add_left(Mode::new());
+    line.add_left(GitBranch);
+    line.add_right(Diagnostic::new(true));
+
+    assert_eq!(
+        line.render().unwrap(),
+        "%#StatusModeRev#\u{e0b6}%#StatusMode#insert%#StatusModeRev#\u{e0b4}%* \
+         %#StatusGitSep#\u{e0b6}%#StatusGit#\u{e725} %#StatusGitRev# main%*\u{e0b4}%*\
+         %=  %=\
+         %#StatusDiagnosticGlobalSep#\u{e0b6}%#StatusDiagnosticGlobal#\u{f024b} \
+         %#StatusDiagnosticGlobalRev# %#StatusError#\u{f530} 2 %#StatusWarn#\u{f071} 1\
+         %*\u{e0b4}%*"
+    );
+    assert_eq!(
+        fake.highlights.borrow().get("StatusMode"),
+        Some(&HighlightOpt::with_bg(Color::Green).fg_if_none(STATUS_LINE_BG))
+    );
+}
+
+#[test]
+fn plain_statusline_snapshot() {
+    FakeNvim {
+        mode: Cell::new(crate::Mode::Insert),
+        diagnostics: [2, 1, 0, 0],
+        git_head: Some("main"),
+        ..Default::default()
+    }
+    .install();
+
+    let mut line = Line::new();
+    line.full_width();
+    line.set_style(TileStyle::Plain);
+    line.add_left(Mode::new());
+    line.add_left(GitBranch);
+    line.add_right(Diagnostic::new(true));
+    line.add_right(Failing);
+
+    assert_eq!(
+        line.render().unwrap(),
+        "%#StatusModeRev#[insert]%* %#StatusGitRev#[main]%*%=  %=\
+         %#StatusDiagnosticGlobalRev#[%#StatusError#E 2 %#StatusWarn#W 1]%* \
+         %#StatusTileError#!%*"
+    );
+}
+
+#[test]
+fn empty_tiles_are_skipped() {
+    FakeNvim::default().install();
+
+    let mut line = Line::new();
+    line.full_width();
+    line.add_left(Mode::new());
+    line.add_left(GitBranch);
+    line.add_left(Diagnostic::new(true));
+    line.add_right(GitDiff);
+    line.add_right(Text::new("Right", "right"));
+
+    assert_eq!(
+        line.render().unwrap(),
+        "%#StatusModeRev#\u{e0b6}%#StatusMode#normal%#StatusModeRev#\u{e0b4}%*\
+         %=  %=\
+         %#StatusRightRev#\u{e0b6}%#StatusRight#right%#StatusRightRev#\u{e0b4}%*"
+    );
+}
+
+#[test]
+fn center_is_equalized() {
+    FakeNvim {
+        width: 60,
+        ..Default::default()
+    }
+    .install();
+
+    let mut line = Line::new();
+    line.add_left(Text::new("Left", "ab"));
+    line.add_left_center(Text::new("LeftCenter", "L"));
+    line.add_center(Text::new("Center", "center"));
+    line.add_right_center(Text::new("RightCenter", "right"));
+    line.add_right(Text::new("Right", "right side"));
+
+    assert_eq!(
+        line.render().unwrap(),
+        "%#StatusLeftRev#\u{e0b6}%#StatusLeft#ab%#StatusLeftRev#\u{e0b4}%*        \
+         %=    %#StatusLeftCenterRev#\u{e0b6}%#StatusLeftCenter#L%#StatusLeftCenterRev#\u{e0b4}%* \
+         %#StatusCenterRev#\u{e0b6}%#StatusCenter#center%#StatusCenterRev#\u{e0b4}%* \
+         %#StatusRightCenterRev#\u{e0b6}%#StatusRightCenter#right%#StatusRightCenterRev#\u{e0b4}%*\
+         %=%#StatusRightRev#\u{e0b6}%#StatusRight#right side%#StatusRightRev#\u{e0b4}%*"
+    );
+}
+
+#[test]
+fn lowest_priority_is_compacted_then_hidden() {
+    let line = || {
+        let mut line = Line::new();
+        line.full_width();
+        line.add_left(Text {
+            compact: Some("a"),
+            priority: 10,
+            ..Text::new("A", "aaaaaaaa")
+        });
+        line.add_left(Text {
+            priority: 20,
+            ..Text::new("B", "bbbb")
+        });
+        line.add_right(Text {
+            priority: 90,
+            ..Text::new("C", "cccc")
+        });
+        line
+    };
+
+    FakeNvim {
+        width: 20,
+        ..Default::default()
+    }
+    .install();
+    assert_eq!(
+        line().render().unwrap(),
+        "%#StatusARev#\u{e0b6}%#StatusA#a%#StatusARev#\u{e0b4}%* \
+         %#StatusBRev#\u{e0b6}%#StatusB#bbbb%#StatusBRev#\u{e0b4}%*\
+         %=  %=\
+         %#StatusCRev#\u{e0b6}%#StatusC#cccc%#StatusCRev#\u{e0b4}%*"
+    );
+
+    FakeNvim {
+        width: 12,
+        ..Default::default()
+    }
+    .install();
+    assert_eq!(
+        line().render().unwrap(),
+        "%=  %=%#StatusCRev#\u{e0b6}%#StatusC#cccc%#StatusCRev#\u{e0b4}%*"
+    );
+}
+
+#[test]
+fn cache_is_cleared_on_events() {
+    let fake = FakeNvim::default().install();
+
+    let mut line = Line::new();
+    line.full_width();
+    line.add_left(Mode::new());
+    let normal = line.render().unwrap();
+
+    fake.mode.set(crate::Mode::Insert);
+    assert_eq!(line.render().unwrap(), normal);
+
+    fake.fire("ModeChanged");
+    assert_eq!(
+        line.render().unwrap(),
+        "%#StatusModeRev#\u{e0b6}%#StatusMode#insert%#StatusModeRev#\u{e0b4}%*%=  %="
+    );
+}
+
+#[test]
+fn excluded_filetypes_render_nothing() {
+    FakeNvim {
+        filetype: "oil",
+        ..Default::default()
+    }
+    .install();
+
+    let mut line = Line::new();
+    line.add_left(Text::new("Left", "left"));
+    line.exclude_ft("oil");
+
+    assert_eq!(line.render().unwrap(), "");
+}
+
+#[test]
+fn failing_tiles_are_disabled() {
+    let fake = FakeNvim::default().install();
+
+    let mut line = Line::new();
+    line.full_width();
+    line.add_left(Failing);
+    line.add_right(Text::new("Right", "right"));
+    let right = "%#StatusRightRev#\u{e0b6}%#StatusRight#right%#StatusRightRev#\u{e0b4}%*";
+
+    for _ in 1..MAX_TILE_FAILURES {
+        assert_eq!(
+            line.render().unwrap(),
+            format!("%#StatusTileError#\u{f530}%*%=  %={right}")
+        );
+    }
+    assert_eq!(line.render().unwrap(), format!("%=  %={right}"));
+    assert_eq!(line.render().unwrap(), format!("%=  %={right}"));
+    assert_eq!(fake.notifications.borrow().len(), 1);
+}
+
+#[test]
+fn disabled_tiles_are_retried() {
+    let fake = FakeNvim::default().install();
+
+    let mut line = Line::new();
+    line.full_width();
+    line.add_left(LateSetup(Cell::new(false)));
+    line.add_right(Failing);
+
+    assert_eq!(line.render().unwrap(), "%=  %=%#StatusTileError#\u{f530}%*");
+    for _ in 1..MAX_TILE_FAILURES {
+        line.render().unwrap();
+    }
+    assert_eq!(line.render().unwrap(), "%=  %=");
+
+    // pretend the retry delay passed
+    let retry_at = Instant::now().checked_sub(TILE_RETRY_DELAY);
+    line.left[0].3.disabled_at = retry_at;
+    line.right[0].3.disabled_at = retry_at;
+    assert_eq!(
+        line.render().unwrap(),
+        "%#StatusLateRev#\u{e0b6}%#StatusLate#late%#StatusLateRev#\u{e0b4}%*%=  \
+         %=%#StatusTileError#\u{f530}%*"
+    );
+    assert_eq!(fake.notifications.borrow().len(), 2);
+}
+
+#[test]
+fn hidden_lines_and_tiles() {
+    let line = || {
+        let mut line = Line::new();
+        line.add_left(Mode::new());
+        line.add_right(Text::new("Right", "right"));
+        line.hide_if(|win| win.float || win.buftype == "help");
+        line.hide_tile_if("Text", |win| win.width < 40);
+        line
+    };
+    let mode = "%#StatusModeRev#\u{e0b6}%#StatusMode#normal%#StatusModeRev#\u{e0b4}%*";
+
+    FakeNvim::default().install();
+    assert_eq!(
+        line().render().unwrap(),
+        format!(
+            "{mode}%=  %=%#StatusRightRev#\u{e0b6}%#StatusRight#right%#StatusRightRev#\u{e0b4}%*"
+        )
+    );
+
+    FakeNvim {
+        width: 30,
+        ..Default::default()
+    }
+    .install();
+    assert_eq!(line().render().unwrap(), format!("{mode}%=  %="));
+
+    FakeNvim {
+        float: true,
+        ..Default::default()
+    }
+    .install();
+    assert_eq!(line().render().unwrap(), "");
+
+    FakeNvim {
+        buftype: "help",
+        ..Default::default()
+    }
+    .install();
+    assert_eq!(line().render().unwrap(), "");
+}
+
+#[test]
+fn layout_tiles_are_hidden_by_their_layout_name() {
+    let mut registry = TileRegistry::new();
+    registry.register("mode", Mode::new);
+    registry.register("right_text", || Text::new("Right", "right"));
+    let line = |name: &'static str| {
+        let mut line = Line::from_layout("mode > right_text", &registry).unwrap();
+        line.hide_tile_if(name, |win| win.width < 40);
+        line
+    };
+    let mode = "%#StatusModeRev#\u{e0b6}%#StatusMode#normal%#StatusModeRev#\u{e0b4}%*";
+    let right = "%#StatusRightRev#\u{e0b6}%#StatusRight#right%#StatusRightRev#\u{e0b4}%*";
+
+    FakeNvim {
+        width: 30,
+        ..Default::default()
+    }
+    .install();
+    assert_eq!(
+        line("right_text").render().unwrap(),
+        format!("{mode}%=  %=")
+    );
+    assert_eq!(
+        line("Text").render().unwrap(),
+        format!("{mode}%=  %={right}")
+    );
+}
+
+#[test]
+fn fill_tiles_take_the_width_left() {
+    FakeNvim {
+        width: 40,
+        ..Default::default()
+    }
+    .install();
+
+    let mut line = Line::new();
+    line.full_width();
+    line.add_left(Fill(0));
+    line.add_right(Text::new("Right", "right"));
+
+    let filled = |rendered: String| rendered.matches('x').count();
+    // the right tile, the 2 spaces around the empty center and the space between the tiles leave
+    // 30 cells
+    assert_eq!(filled(line.render().unwrap()), 28);
+
+    FakeNvim {
+        width: 30,
+        ..Default::default()
+    }
+    .install();
+    assert_eq!(filled(line.render().unwrap()), 18);
+}
+
+#[test]
+fn closed_windows_are_pruned_from_the_cache() {
+    let fake = FakeNvim::default().install();
+
+    let mut line = Line::new();
+    line.add_left(Fill(10));
+    line.render().unwrap();
+    assert_eq!(line.left[0].2.0.borrow().len(), 1);
+
+    fake.close_win(1001);
+    assert_eq!(line.left[0].2.0.borrow().len(), 1);
+
+    fake.close_win(1000);
+    assert!(line.left[0].2.0.borrow().is_empty());
+}
+
+#[test]
+fn indent_is_detected() {
+    let cases: &[(&str, &[&str], Option<Indent>)] = &[
+        ("empty", &[], None),
+        ("not indented", &["a", "", "b"], None),
+        (
+            "tabs",
+            &["fn a() {", "\tb", "\t\tc", "\tb", "}"],
+            Some(Indent::Tabs),
+        ),
+        (
+            "2 spaces",
+            &["a:", "  b:", "    c", "  d"],
+            Some(Indent::Spaces(2)),
+        ),
+        (
+            "4 spaces",
+            &["fn a() {", "    b", "    if c {", "        d", "    }", "}"],
+            Some(Indent::Spaces(4)),
+        ),
+        (
+            "mostly tabs",
+            &["a", "\tb", "\tc", "  d"],
+            Some(Indent::Tabs),
+        ),
+        (
+            "mostly spaces",
+            &["a", "\tb", "  c", "  d", "    e"],
+            Some(Indent::Spaces(2)),
+        ),
+        (
+            "block comments",
+            &["/**", " * doc", " */", "fn a() {", "    b", "}"],
+            Some(Indent::Spaces(4)),
+        ),
+        ("tie", &["a", "  b", "a", "    c"], Some(Indent::Spaces(2))),
+    ];
+
+    for (name, lines, expected) in cases {
+        let lines = lines.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(detect_indent(&lines), *expected, "{name}");
+    }
+}
+
+#[test]
+fn words_are_counted() {
+    let cases: &[(&str, bool, &[&str], usize)] = &[
+        ("empty", false, &[], 0),
+        (
+            "markdown",
+            false,
+            &[
+                "# Title here",
+                "Some words, and more.",
+                "",
+                "```rust",
+                "let x = 1;",
+                "```",
+                "- item one",
+                "~~~",
+                "code",
+                "~~~",
+            ],
+            8,
+        ),
+        ("markdown hash in words", false, &["C# is #1"], 3),
+        (
+            "typst",
+            true,
+            &[
+                "#set page(width: 10cm)",
+                "#let x = 1",
+                "= Heading",
+                "Some #emph[important] text <label>",
+                "See @ref and #v(1em) more",
+                "// comment",
+                "```",
+                "code",
+                "```",
+            ],
+            7,
+        ),
+        (
+            "typst call at line start",
+            true,
+            &["#emph[two words] after"],
+            3,
+        ),
+    ];
+
+    for (name, typst, lines, expected) in cases {
+        let lines = lines.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(count_words(&lines, *typst), *expected, "{name}");
+    }
+}
@@ -35,14 +35,99 @@ pub(crate) fn highlights() -> nvim_oxi::Result<()> {
 
     set_hl("@type.builtin", HighlightOpt::link(Type))?;
 
-    set_lsp_hl("property", HighlightOpt::link(Member))?;
-    set_lsp_hl("modifier", HighlightOpt::link(Keyword))?;
+    lsp_highlights()?;
+
+    set_hl("rustSigil", HighlightOpt::link(Operator))?;
+
+    Ok(())
+}
 
+/// Map the LSP semantic token types and modifiers onto the palette. This include the standard token
+/// from the LSP spec as well as the extra one emitted by rust-analyzer.
+///
+/// Type highlights (`@lsp.type.*`) link to a syntax group while modifier highlights
+/// (`@lsp.mod.*`) only set a style so they combine with the color of the type.
+#[rustfmt::skip]
+fn lsp_highlights() -> nvim_oxi::Result<()> {
+    use SyntaxGroup::*;
+
+    // Types
+    set_lsp_hl("namespace", HighlightOpt::with_fg(Yellow))?;
+    set_lsp_hl("type", HighlightOpt::link(Type))?;
+    set_lsp_hl("class", HighlightOpt::link(Structure))?;
     set_lsp_hl("struct", HighlightOpt::link(Structure))?;
     set_lsp_hl("enum", HighlightOpt::link(Structure))?;
-    set_lsp_hl("enumMember", HighlightOpt::with_fg(Yellow).italic())?;
+    set_lsp_hl("union", HighlightOpt::link(Structure))?;
+    set_lsp_hl("interface", HighlightOpt::link(Type))?;
+    set_lsp_hl("typeAlias", HighlightOpt::link(Type))?;
+    set_lsp_hl("typeParameter", HighlightOpt::with_fg(Orange).italic())?;
+    set_lsp_hl("builtinType", HighlightOpt::link(Type))?;
+    set_lsp_hl("selfTypeKeyword", HighlightOpt::link(Type))?;
 
-    set_hl("rustSigil", HighlightOpt::link(Operator))?;
+    set_lsp_hl("enumMember", HighlightOpt::with_fg(Yellow).italic())?;
+    set_lsp_hl("property", HighlightOpt::link(Member))?;
+    set_lsp_hl("variable", HighlightOpt::link(Identifier))?;
+    set_lsp_hl("parameter", HighlightOpt::link(Identifier))?;
+    set_lsp_hl("selfKeyword", HighlightOpt::link(Builtin))?;
+    set_lsp_hl("const", HighlightOpt::link(Constant))?;
+    set_lsp_hl("constParameter", HighlightOpt::link(Constant))?;
+    set_lsp_hl("static", HighlightOpt::link(Constant))?;
+
+    set_lsp_hl("function", HighlightOpt::link(Function))?;
+    set_lsp_hl("method", HighlightOpt::link(Function))?;
+    set_lsp_hl("macro", HighlightOpt::link(Preproc))?;
+    set_lsp_hl("procMacro", HighlightOpt::link(Preproc))?;
+    set_lsp_hl("derive", HighlightOpt::link(Preproc))?;
+    set_lsp_hl("deriveHelper", HighlightOpt::link(Preproc))?;
+    set_lsp_hl("attribute", HighlightOpt::link(Preproc))?;
+    set_lsp_hl("builtinAttribute", HighlightOpt::link(Preproc))?;
+    set_lsp_hl("decorator", HighlightOpt::link(Preproc))?;
+
+    set_lsp_hl("keyword", HighlightOpt::link(Keyword))?;
+    set_lsp_hl("modifier", HighlightOpt::link(Keyword))?;
+    set_lsp_hl("lifetime", HighlightOpt::with_fg(Pink).italic())?;
+    set_lsp_hl("label", HighlightOpt::with_fg(Pink).italic())?;
+
+    set_lsp_hl("comment", HighlightOpt::link(Comment))?;
+    set_lsp_hl("string", HighlightOpt::link(String))?;
+    set_lsp_hl("number", HighlightOpt::link(Number))?;
+    set_lsp_hl("boolean", HighlightOpt::link(Boolean))?;
+    set_lsp_hl("character", HighlightOpt::link(Character))?;
+    set_lsp_hl("regexp", HighlightOpt::link(Special))?;
+    set_lsp_hl("escapeSequence", HighlightOpt::link(Special))?;
+    set_lsp_hl("formatSpecifier", HighlightOpt::link(Special))?;
+    set_lsp_hl("invalidEscapeSequence", HighlightOpt::link("DiagnosticUnderlineError"))?;
+
+    set_lsp_hl("operator", HighlightOpt::link(Operator))?;
+    set_lsp_hl("punctuation", HighlightOpt::link(Delimiter))?;
+    set_lsp_hl("unresolvedReference", HighlightOpt::link("DiagnosticUnderlineError"))?;
+
+    // Modifiers
+    set_lsp_mod_hl("mutable", HighlightOpt::default().underline())?;
+    set_lsp_mod_hl("deprecated", HighlightOpt::default().strike())?;
+    set_lsp_mod_hl("async", HighlightOpt::default().italic())?;
+    set_lsp_mod_hl("consuming", HighlightOpt::default().bold())?;
+    set_lsp_mod_hl("unsafe", HighlightOpt::with_fg(Red).bold())?;
+
+    // Type and modifier combinations
+    set_lsp_typemod_hl("variable", "static", HighlightOpt::link(Constant))?;
+    set_lsp_typemod_hl("variable", "constant", HighlightOpt::link(Constant))?;
+    set_lsp_typemod_hl("variable", "readonly", HighlightOpt::link(Constant))?;
+    set_lsp_typemod_hl("variable", "defaultLibrary", HighlightOpt::link(Builtin))?;
+    set_lsp_typemod_hl("variable", "callable", HighlightOpt::link(Function))?;
+    set_lsp_typemod_hl("parameter", "callable", HighlightOpt::link(Function))?;
+
+    set_lsp_typemod_hl("function", "unsafe", HighlightOpt::with_fg(Red).bold())?;
+    set_lsp_typemod_hl("method", "unsafe", HighlightOpt::with_fg(Red).bold())?;
+    set_lsp_typemod_hl("operator", "unsafe", HighlightOpt::with_fg(Red).bold())?;
+    set_lsp_typemod_hl("keyword", "unsafe", HighlightOpt::with_fg(Red).bold())?;
+    set_lsp_typemod_hl("keyword", "controlFlow", HighlightOpt::with_fg(Purple).italic())?;
+
+    set_lsp_typemod_hl("method", "trait", HighlightOpt::with_fg(Blue).italic())?;
+    set_lsp_typemod_hl("function", "trait", HighlightOpt::with_fg(Blue).italic())?;
+    set_lsp_typemod_hl("interface", "trait", HighlightOpt::with_fg(Orange).italic())?;
+
+    set_lsp_typemod_hl("comment", "documentation", HighlightOpt::with_fg(Gray).italic())?;
 
     Ok(())
 }
@@ -101,3 +186,13 @@ fn set_lsp_hl(hl: &str, opt: HighlightOpt) -> nvim_oxi::Result<()> {
     set_hl(format!("@lsp.type.{hl}").as_str(), opt)?;
     Ok(())
 }
+
+fn set_lsp_mod_hl(modifier: &str, opt: HighlightOpt) -> nvim_oxi::Result<()> {
+    set_hl(format!("@lsp.mod.{modifier}").as_str(), opt)?;
+    Ok(())
+}
+
+fn set_lsp_typemod_hl(hl: &str, modifier: &str, opt: HighlightOpt) -> nvim_oxi::Result<()> {
+    set_hl(format!("@lsp.typemod.{hl}.{modifier}").as_str(), opt)?;
+    Ok(())
+}